@@ -1,12 +1,48 @@
+use crate::downloader::template::DEFAULT_TEMPLATE;
+use crate::downloader::{
+    EmbedOptions, RateLimit, RetryPolicy, SiteRule, SubtitleOptions, TimeWindow,
+};
+use rusqlite::{params, Connection, Row};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Settings {
     pub quality: String,
     pub down_video_list: String,
+    /// 播放列表是否放进单独的文件夹并生成 .m3u8
+    pub playlist_folder: String,
     pub download_path: String,
+    /// 文件名模板（yt-dlp 输出模板语法），相对于下载目录
+    pub filename_template: String,
+    /// 同时进行的下载任务数
+    pub max_concurrent_downloads: usize,
+    /// 只在该时段内下载，时段结束时暂停正在运行的任务
+    pub download_window: TimeWindow,
+    /// 播放列表、频道与订阅是否使用下载存档跳过已下载的视频，单个视频总是下载
+    pub use_archive: String,
+    /// 取消下载时是否删除 .part/.ytdl 等临时文件
+    pub clean_partial_on_cancel: String,
+    pub retry: RetryPolicy,
+    /// 全局限速（`--limit-rate`）与按时段的限速方案
+    pub rate_limit: RateLimit,
+    /// `--proxy`，如 `http://127.0.0.1:7890`、`socks5://127.0.0.1:1080`，为空表示不指定
+    pub proxy: String,
+    /// 订阅检查间隔（分钟）
+    pub subscription_interval: u64,
+    /// `video` 下载视频，`audio` 只提取音频
+    pub download_mode: String,
+    /// 提取音频时的格式：mp3、m4a、opus、flac
+    pub audio_format: String,
+    /// 传给 `--audio-quality`，`0` 为最佳，或 `192K` 这样的码率
+    pub audio_quality: String,
+    pub subtitles: SubtitleOptions,
+    pub embed: EmbedOptions,
+    /// 按顺序匹配的站点规则
+    pub site_rules: Vec<SiteRule>,
 }
 
 impl Default for Settings {
@@ -14,9 +50,25 @@ impl Default for Settings {
         Self {
             quality: "normal".to_string(),
             down_video_list: "no".to_string(),
+            playlist_folder: "no".to_string(),
             download_path: dirs_next::download_dir()
                 .map(|p| p.to_string_lossy().to_string())
                 .unwrap_or_default(),
+            filename_template: DEFAULT_TEMPLATE.to_string(),
+            max_concurrent_downloads: 3,
+            download_window: TimeWindow::default(),
+            use_archive: "yes".to_string(),
+            clean_partial_on_cancel: "yes".to_string(),
+            retry: RetryPolicy::default(),
+            rate_limit: RateLimit::default(),
+            proxy: String::new(),
+            subscription_interval: 60,
+            download_mode: "video".to_string(),
+            audio_format: "mp3".to_string(),
+            audio_quality: "0".to_string(),
+            subtitles: SubtitleOptions::default(),
+            embed: EmbedOptions::default(),
+            site_rules: SiteRule::defaults(),
         }
     }
 }
@@ -45,3 +97,470 @@ impl Settings {
         }
     }
 }
+
+/// 按顺序执行的建表/升级语句，已执行的版本记录在 `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE downloads (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        url         TEXT NOT NULL,
+        title       TEXT,
+        site        TEXT,
+        output_path TEXT,
+        size        INTEGER,
+        format      TEXT,
+        started_at  INTEGER NOT NULL,
+        finished_at INTEGER,
+        status      TEXT NOT NULL,
+        error       TEXT
+    );
+    CREATE INDEX idx_downloads_started_at ON downloads(started_at);",
+    "CREATE TABLE subscriptions (
+        id            INTEGER PRIMARY KEY AUTOINCREMENT,
+        url           TEXT NOT NULL UNIQUE,
+        name          TEXT,
+        quality       TEXT,
+        download_path TEXT,
+        enabled       INTEGER NOT NULL DEFAULT 1,
+        created_at    INTEGER NOT NULL,
+        last_checked  INTEGER,
+        last_error    TEXT
+    );",
+];
+
+/// 下载记录的状态
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_PAUSED: &str = "paused";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_CANCELLED: &str = "cancelled";
+pub const STATUS_SKIPPED: &str = "skipped";
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub site: Option<String>,
+    pub output_path: Option<String>,
+    pub size: Option<u64>,
+    pub format: Option<String>,
+    /// Unix 时间戳（秒）
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            url: row.get("url")?,
+            title: row.get("title")?,
+            site: row.get("site")?,
+            output_path: row.get("output_path")?,
+            size: row.get("size")?,
+            format: row.get("format")?,
+            started_at: row.get("started_at")?,
+            finished_at: row.get("finished_at")?,
+            status: row.get("status")?,
+            error: row.get("error")?,
+        })
+    }
+}
+
+/// 下载完成后写入的文件信息
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FinishedDownload {
+    pub title: Option<String>,
+    pub site: Option<String>,
+    pub output_path: Option<String>,
+    pub size: Option<u64>,
+    pub format: Option<String>,
+}
+
+/// 订阅的频道或播放列表，定期检查新上传的视频
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Subscription {
+    pub id: i64,
+    pub url: String,
+    pub name: Option<String>,
+    /// 覆盖设置中的画质，`None` 表示跟随设置
+    pub quality: Option<String>,
+    /// 覆盖设置中的下载路径
+    pub download_path: Option<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+    /// 上次检查的时间，从未检查过为 `None`
+    pub last_checked: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+impl Subscription {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            url: row.get("url")?,
+            name: row.get("name")?,
+            quality: row.get("quality")?,
+            download_path: row.get("download_path")?,
+            enabled: row.get("enabled")?,
+            created_at: row.get("created_at")?,
+            last_checked: row.get("last_checked")?,
+            last_error: row.get("last_error")?,
+        })
+    }
+}
+
+/// 下载历史数据库，同时保存订阅
+pub struct HistoryDb {
+    conn: Connection,
+}
+
+impl HistoryDb {
+    pub fn db_path() -> PathBuf {
+        dirs_next::config_dir()
+            .unwrap_or_else(|| std::env::current_dir().unwrap())
+            .join("zdownload_history.db")
+    }
+
+    pub fn open() -> rusqlite::Result<Self> {
+        Self::open_at(&Self::db_path())
+    }
+
+    pub fn open_at(path: &Path) -> rusqlite::Result<Self> {
+        let mut db = Self {
+            conn: Connection::open(path)?,
+        };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    fn migrate(&mut self) -> rusqlite::Result<()> {
+        let version: usize = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(sql)?;
+            tx.pragma_update(None, "user_version", index + 1)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// 记录一次开始的下载，返回记录编号
+    pub fn record_start(&self, url: &str) -> rusqlite::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO downloads (url, started_at, status) VALUES (?1, ?2, ?3)",
+            params![url, now(), STATUS_RUNNING],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 写入下载结果；`files` 为空时只更新状态，
+    /// 多个文件（如播放列表）时第一个文件写入原记录，其余各自新增一条。
+    /// 全部写入同一个事务，中途失败不会留下一半的记录
+    pub fn record_finish(
+        &mut self,
+        id: i64,
+        status: &str,
+        error: Option<&str>,
+        files: &[FinishedDownload],
+    ) -> rusqlite::Result<()> {
+        let finished_at = now();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "UPDATE downloads SET finished_at = ?2, status = ?3, error = ?4 WHERE id = ?1",
+            params![id, finished_at, status, error],
+        )?;
+
+        for (index, file) in files.iter().enumerate() {
+            let row_id = if index == 0 {
+                id
+            } else {
+                tx.execute(
+                    "INSERT INTO downloads (url, started_at, finished_at, status, error)
+                     SELECT url, started_at, finished_at, status, error FROM downloads WHERE id = ?1",
+                    params![id],
+                )?;
+                tx.last_insert_rowid()
+            };
+            tx.execute(
+                "UPDATE downloads
+                 SET title = ?2, site = ?3, output_path = ?4, size = ?5, format = ?6
+                 WHERE id = ?1",
+                params![
+                    row_id,
+                    file.title,
+                    file.site,
+                    file.output_path,
+                    file.size,
+                    file.format
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// 最近的记录，按开始时间倒序
+    pub fn recent(&self, limit: usize) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT * FROM downloads ORDER BY started_at DESC, id DESC LIMIT ?1")?;
+        let rows = stmt.query_map(params![limit], HistoryEntry::from_row)?;
+        rows.collect()
+    }
+
+    /// 按标题、链接或站点模糊搜索
+    pub fn search(&self, keyword: &str, limit: usize) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let pattern = format!("%{}%", keyword);
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM downloads
+             WHERE title LIKE ?1 OR url LIKE ?1 OR site LIKE ?1
+             ORDER BY started_at DESC, id DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![pattern, limit], HistoryEntry::from_row)?;
+        rows.collect()
+    }
+
+    pub fn delete(&self, id: i64) -> rusqlite::Result<()> {
+        self.conn
+            .execute("DELETE FROM downloads WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn clear(&self) -> rusqlite::Result<()> {
+        self.conn.execute("DELETE FROM downloads", [])?;
+        Ok(())
+    }
+
+    pub fn add_subscription(&self, url: &str, name: Option<&str>) -> rusqlite::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO subscriptions (url, name, created_at) VALUES (?1, ?2, ?3)",
+            params![url, name, now()],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn subscriptions(&self) -> rusqlite::Result<Vec<Subscription>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT * FROM subscriptions ORDER BY created_at, id")?;
+        let rows = stmt.query_map([], Subscription::from_row)?;
+        rows.collect()
+    }
+
+    /// 保存可编辑的字段：名称、画质、路径与启用状态
+    pub fn update_subscription(&self, subscription: &Subscription) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE subscriptions
+             SET name = ?2, quality = ?3, download_path = ?4, enabled = ?5
+             WHERE id = ?1",
+            params![
+                subscription.id,
+                subscription.name,
+                subscription.quality,
+                subscription.download_path,
+                subscription.enabled
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 订阅没有名称时使用频道或播放列表的标题
+    pub fn fill_subscription_name(&self, id: i64, name: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE subscriptions SET name = ?2 WHERE id = ?1 AND (name IS NULL OR name = '')",
+            params![id, name],
+        )?;
+        Ok(())
+    }
+
+    /// 链接下载失败的次数与最近一次失败的开始时间
+    pub fn failures(&self, url: &str) -> rusqlite::Result<(u32, Option<i64>)> {
+        self.conn.query_row(
+            "SELECT COUNT(*), MAX(started_at) FROM downloads WHERE url = ?1 AND status = ?2",
+            params![url, STATUS_FAILED],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    /// 记录一次检查的结果
+    pub fn mark_checked(&self, id: i64, error: Option<&str>) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE subscriptions SET last_checked = ?2, last_error = ?3 WHERE id = ?1",
+            params![id, now(), error],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_subscription(&self, id: i64) -> rusqlite::Result<()> {
+        self.conn
+            .execute("DELETE FROM subscriptions WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_db() -> HistoryDb {
+        HistoryDb::open_at(Path::new(":memory:")).unwrap()
+    }
+
+    fn user_version(db: &HistoryDb) -> usize {
+        db.conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn file(title: &str, path: &str) -> FinishedDownload {
+        FinishedDownload {
+            title: Some(title.to_string()),
+            site: Some("Youtube".to_string()),
+            output_path: Some(path.to_string()),
+            size: Some(1024),
+            format: Some("137+140".to_string()),
+        }
+    }
+
+    #[test]
+    fn migrates_new_database() {
+        let mut db = memory_db();
+        assert_eq!(user_version(&db), MIGRATIONS.len());
+        // 再次执行不会重复建表
+        db.migrate().unwrap();
+        assert_eq!(user_version(&db), MIGRATIONS.len());
+        assert!(db.recent(10).unwrap().is_empty());
+        assert!(db.subscriptions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn upgrades_old_database() {
+        let path = std::env::temp_dir().join(format!("zdownload-db-{}.db", std::process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(MIGRATIONS[0]).unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+            conn.execute(
+                "INSERT INTO downloads (url, started_at, status) VALUES ('https://a', 1, 'completed')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let db = HistoryDb::open_at(&path).unwrap();
+        assert_eq!(user_version(&db), MIGRATIONS.len());
+        assert_eq!(db.recent(10).unwrap()[0].url, "https://a");
+        db.add_subscription("https://www.youtube.com/@a", None)
+            .unwrap();
+        assert_eq!(db.subscriptions().unwrap().len(), 1);
+        drop(db);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn records_one_row_per_file() {
+        let mut db = memory_db();
+        let id = db
+            .record_start("https://www.youtube.com/playlist?list=x")
+            .unwrap();
+        db.record_finish(
+            id,
+            STATUS_COMPLETED,
+            None,
+            &[file("第一集", "/dl/1.mp4"), file("第二集", "/dl/2.mp4")],
+        )
+        .unwrap();
+
+        let entries = db.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.status == STATUS_COMPLETED));
+        assert!(entries.iter().all(|e| e.finished_at.is_some()));
+        let first = entries.iter().find(|e| e.id == id).unwrap();
+        assert_eq!(first.title.as_deref(), Some("第一集"));
+        assert_eq!(first.size, Some(1024));
+
+        let failed = db.record_start("https://b").unwrap();
+        db.record_finish(failed, STATUS_FAILED, Some("网络错误"), &[])
+            .unwrap();
+        let entry = db.recent(1).unwrap().remove(0);
+        assert_eq!(entry.status, STATUS_FAILED);
+        assert_eq!(entry.error.as_deref(), Some("网络错误"));
+        assert!(entry.title.is_none());
+    }
+
+    #[test]
+    fn searches_title_url_and_site() {
+        let mut db = memory_db();
+        let id = db
+            .record_start("https://www.bilibili.com/video/BV1")
+            .unwrap();
+        db.record_finish(
+            id,
+            STATUS_COMPLETED,
+            None,
+            &[file("猫咪合集", "/dl/cat.mp4")],
+        )
+        .unwrap();
+        db.record_start("https://www.youtube.com/watch?v=abc")
+            .unwrap();
+
+        assert_eq!(db.search("猫咪", 10).unwrap().len(), 1);
+        assert_eq!(db.search("youtube", 10).unwrap().len(), 2);
+        assert_eq!(db.search("bilibili", 10).unwrap().len(), 1);
+        assert!(db.search("不存在", 10).unwrap().is_empty());
+        assert_eq!(db.search("", 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn counts_failures() {
+        let mut db = memory_db();
+        assert_eq!(db.failures("https://a").unwrap(), (0, None));
+
+        for status in [STATUS_FAILED, STATUS_COMPLETED, STATUS_FAILED] {
+            let id = db.record_start("https://a").unwrap();
+            db.record_finish(id, status, None, &[]).unwrap();
+        }
+        db.record_start("https://b").unwrap();
+
+        let (count, last) = db.failures("https://a").unwrap();
+        assert_eq!(count, 2);
+        assert!(last.is_some_and(|t| t > 0));
+        assert_eq!(db.failures("https://b").unwrap(), (0, None));
+    }
+
+    #[test]
+    fn manages_subscriptions() {
+        let db = memory_db();
+        let id = db
+            .add_subscription("https://www.youtube.com/@a", None)
+            .unwrap();
+        assert!(db
+            .add_subscription("https://www.youtube.com/@a", None)
+            .is_err());
+
+        db.fill_subscription_name(id, "频道 A").unwrap();
+        db.fill_subscription_name(id, "另一个名称").unwrap();
+        db.mark_checked(id, Some("超时")).unwrap();
+        let subscription = db.subscriptions().unwrap().remove(0);
+        assert_eq!(subscription.name.as_deref(), Some("频道 A"));
+        assert!(subscription.enabled);
+        assert!(subscription.last_checked.is_some());
+        assert_eq!(subscription.last_error.as_deref(), Some("超时"));
+
+        db.delete_subscription(id).unwrap();
+        assert!(db.subscriptions().unwrap().is_empty());
+    }
+}
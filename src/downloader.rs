@@ -0,0 +1,653 @@
+//! 与 UI 无关的下载引擎：负责释放内置的 yt-dlp / ffmpeg、拼装命令行参数、
+//! 启动进程并把输出转换成类型化的事件流。桌面端、命令行或测试都可以直接驱动它。
+pub mod archive;
+mod clip;
+mod embed;
+pub mod metadata;
+pub mod partial;
+pub mod playlist;
+mod process;
+pub mod progress;
+pub mod proxy;
+mod queue;
+mod ratelimit;
+mod retry;
+pub mod rules;
+pub mod schedule;
+pub mod subscriptions;
+mod subtitles;
+pub mod template;
+
+use crate::db::Settings;
+use serde::{Deserialize, Serialize};
+use std::env::temp_dir;
+use std::{
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicU64, Ordering},
+    sync::mpsc::{self, Receiver, RecvError, Sender},
+    sync::Mutex,
+    thread,
+};
+
+pub use clip::ClipRange;
+pub use embed::EmbedOptions;
+pub use process::ProcessHandle;
+pub use progress::{Progress, ProgressStage};
+pub use queue::{DownloadQueue, JobStatus, QueuedJob};
+pub use ratelimit::{parse_rate, RateLimit, RateProfile};
+pub use retry::RetryPolicy;
+pub use rules::SiteRule;
+pub use schedule::TimeWindow;
+pub use subtitles::SubtitleOptions;
+
+#[cfg(not(windows))]
+const YT_DLP_BYTES: &[u8] = include_bytes!("./downloader/bin/yt-dlp");
+#[cfg(not(windows))]
+const FFMPEG_BYTES: &[u8] = include_bytes!("./downloader/bin/ffmpeg");
+#[cfg(not(windows))]
+const YT_DLP_NAME: &str = "yt-dlp-temp";
+#[cfg(not(windows))]
+const FFMPEG_NAME: &str = "ffmpeg-temp";
+
+#[cfg(windows)]
+const YT_DLP_BYTES: &[u8] = include_bytes!("./downloader/bin/yt-dlp.exe");
+#[cfg(windows)]
+const FFMPEG_BYTES: &[u8] = include_bytes!("./downloader/bin/ffmpeg.exe");
+#[cfg(windows)]
+const YT_DLP_NAME: &str = "yt-dlp-temp.exe";
+#[cfg(windows)]
+const FFMPEG_NAME: &str = "ffmpeg-temp.exe";
+
+/// 一次下载需要的全部参数
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct DownloadRequest {
+    pub url: String,
+    pub download_path: String,
+    pub quality: String,
+    /// 文件名模板，相对于下载目录，为空时使用默认模板
+    pub filename_template: String,
+    pub download_playlist: bool,
+    /// `--playlist-items`，为空表示全部条目
+    pub playlist_items: String,
+    /// 频道首页这类嵌套播放列表中选中的视频 id。
+    /// `--playlist-items` 会作用于每一层列表，这时改用 `--match-filters` 按 id 选择
+    pub playlist_ids: Vec<String>,
+    /// 按倒序下载播放列表
+    pub playlist_reverse: bool,
+    /// 播放列表放进以列表标题命名的文件夹，文件名带序号，并生成 `.m3u8`。
+    /// 只在确认链接是播放列表时设置，否则单个视频会被放进 `NA` 文件夹
+    pub playlist_folder: bool,
+    pub retry: RetryPolicy,
+    /// 手动选择的格式（`-f` 表达式），优先于画质设置
+    pub format: Option<String>,
+    /// 只提取音频，由内置 ffmpeg 转码
+    pub audio_only: bool,
+    pub audio_format: String,
+    pub audio_quality: String,
+    pub subtitles: SubtitleOptions,
+    pub embed: EmbedOptions,
+    /// 按章节拆分成多个文件，仅对本次下载生效
+    pub split_chapters: bool,
+    /// 只下载其中一段，仅对本次下载生效
+    pub clip: ClipRange,
+    /// 使用下载存档，跳过已经下载过的视频。只应对播放列表、频道与订阅开启
+    pub use_archive: bool,
+    /// 订阅检查加入的任务，靠存档判断哪些视频已经下载过
+    pub from_subscription: bool,
+    /// 定时开始（Unix 秒），为空表示立即排队
+    pub start_at: Option<i64>,
+    /// 不受下载时段限制
+    pub ignore_window: bool,
+    pub rate_limit: RateLimit,
+    /// 本次下载的限速，覆盖设置中的限速与时段方案，`0` 表示不限速
+    pub rate_limit_override: Option<String>,
+    /// 设置中的代理，为空表示不指定（yt-dlp 会使用系统环境变量中的代理）
+    pub proxy: String,
+    /// 创建请求时匹配到的站点规则
+    pub site_rule: Option<SiteRule>,
+}
+
+impl DownloadRequest {
+    /// 按当前设置为某个链接生成下载请求
+    pub fn from_settings(url: impl Into<String>, settings: &Settings) -> Self {
+        let url = url.into();
+        Self {
+            site_rule: SiteRule::find(&settings.site_rules, &url).cloned(),
+            url,
+            download_path: settings.download_path.clone(),
+            quality: settings.quality.clone(),
+            filename_template: settings.filename_template.clone(),
+            download_playlist: settings.down_video_list != "no",
+            playlist_items: String::new(),
+            playlist_ids: Vec::new(),
+            playlist_reverse: false,
+            playlist_folder: settings.playlist_folder == "yes",
+            retry: settings.retry.clone(),
+            format: None,
+            audio_only: settings.download_mode == "audio",
+            audio_format: settings.audio_format.clone(),
+            audio_quality: settings.audio_quality.clone(),
+            subtitles: settings.subtitles.clone(),
+            embed: settings.embed.clone(),
+            split_chapters: false,
+            clip: ClipRange::default(),
+            use_archive: settings.use_archive == "yes",
+            from_subscription: false,
+            start_at: None,
+            ignore_window: false,
+            rate_limit: settings.rate_limit.clone(),
+            rate_limit_override: None,
+            proxy: settings.proxy.clone(),
+        }
+    }
+
+    /// 换成另一个链接，其它选项不变，站点规则重新匹配
+    pub fn with_url(self, url: impl Into<String>, settings: &Settings) -> Self {
+        let url = url.into();
+        Self {
+            site_rule: SiteRule::find(&settings.site_rules, &url).cloned(),
+            url,
+            ..self
+        }
+    }
+
+    pub fn output_template(&self) -> String {
+        let template = match self.filename_template.trim() {
+            "" => template::DEFAULT_TEMPLATE,
+            template => template,
+        };
+        // 截取片段时在扩展名前加上区间
+        let template = self.clip.apply_suffix(template);
+        // `%(playlist_index)s` 会按列表长度自动补零
+        let template = if self.playlist_folder && self.download_playlist {
+            format!(
+                "%(playlist_title,playlist)s/%(playlist_index)s - {}",
+                template
+            )
+        } else {
+            template
+        };
+        format!("{}/{}", self.download_path, self.with_subfolder(&template))
+    }
+
+    /// 拆分章节后的文件放在以视频标题命名的文件夹中，文件名带两位曲目序号；
+    /// 截取片段时文件夹名带上区间
+    pub fn chapter_template(&self) -> String {
+        let template = format!(
+            "%(title)s{}/%(section_number)02d - %(section_title)s.%(ext)s",
+            self.clip.file_suffix()
+        );
+        format!("{}/{}", self.download_path, self.with_subfolder(&template))
+    }
+
+    fn with_subfolder(&self, template: &str) -> String {
+        match &self.site_rule {
+            Some(rule) => rule.apply_subfolder(template),
+            None => template.to_string(),
+        }
+    }
+
+    /// 站点规则可以覆盖网络/分片重试次数
+    fn retry_policy(&self) -> RetryPolicy {
+        let mut retry = self.retry.clone();
+        if let Some(retries) = self.site_rule.as_ref().and_then(|r| r.retries) {
+            retry.retries = retries;
+            retry.fragment_retries = retries;
+        }
+        retry
+    }
+
+    /// 检查只对本次下载生效、需要手动填写的选项
+    pub fn validate(&self) -> Result<(), String> {
+        self.clip.parse()?;
+        if let Some(limit) = &self.rate_limit_override {
+            parse_rate(limit)?;
+        }
+        Ok(())
+    }
+
+    /// 当前生效的限速，任务运行中跨过时段边界时由队列重启任务
+    fn current_rate_limit(&self) -> &str {
+        self.rate_limit_at(chrono::Local::now().time())
+    }
+
+    /// `time` 时刻生效的限速，本次下载指定的限速优先
+    fn rate_limit_at(&self, time: chrono::NaiveTime) -> &str {
+        match &self.rate_limit_override {
+            Some(limit) => limit,
+            None => self.rate_limit.current(time),
+        }
+    }
+
+    /// 截取片段、只提取音频或手动选择格式时得到的是另一个文件，
+    /// 不能因为存档中有该视频就跳过，也不写入存档。
+    /// 订阅任务总是使用存档，否则新视频下载后不会记录，每次检查都会再次下载
+    fn archive_enabled(&self) -> bool {
+        if !self.use_archive {
+            return false;
+        }
+        self.from_subscription
+            || (self.clip.is_empty() && !self.audio_only && self.format.is_none())
+    }
+
+    /// 站点规则可以指定另一个代理，或用空字符串强制直连
+    fn proxy_args(&self) -> Vec<String> {
+        let proxy = match self.site_rule.as_ref().and_then(|r| r.proxy.as_ref()) {
+            Some(proxy) => proxy.trim(),
+            None if self.proxy.trim().is_empty() => return Vec::new(),
+            None => self.proxy.trim(),
+        };
+        vec!["--proxy".to_string(), proxy.to_string()]
+    }
+
+    /// 获取视频信息（`-J`）所用的参数
+    pub fn build_info_args(&self) -> Vec<String> {
+        let mut args = vec![
+            self.url.clone(),
+            "-J".to_string(),
+            "--flat-playlist".to_string(),
+            "--no-warnings".to_string(),
+            "--no-check-certificate".to_string(),
+        ];
+        args.extend(self.retry_policy().build_args());
+        args.extend(self.proxy_args());
+        if let Some(rule) = &self.site_rule {
+            args.extend(rule.header_args());
+        }
+        if !self.download_playlist {
+            args.push("--no-playlist".to_string());
+        }
+        args
+    }
+
+    /// 生成传给 yt-dlp 的参数（不含可执行文件本身）。
+    /// 每个下载完成的文件会以一行 JSON 追加到 `record_path`
+    pub fn build_args(&self, ffmpeg_path: &Path, record_path: &Path) -> Vec<String> {
+        let mut args = vec![
+            self.url.clone(),
+            "--newline".to_string(),
+            "--no-check-certificate".to_string(),
+            // 续传 .part 文件，暂停后继续下载依赖于此
+            "--continue".to_string(),
+            "--progress-template".to_string(),
+            progress::PROGRESS_TEMPLATES[0].to_string(),
+            "--progress-template".to_string(),
+            progress::PROGRESS_TEMPLATES[1].to_string(),
+            "--ffmpeg-location".to_string(),
+            ffmpeg_path.to_string_lossy().to_string(),
+            "--print-to-file".to_string(),
+            format!("after_move:{}", DownloadedFile::TEMPLATE),
+            record_path.to_string_lossy().to_string(),
+        ];
+
+        args.extend(self.retry_policy().build_args());
+        args.extend(ratelimit::build_args(self.current_rate_limit()));
+        args.extend(self.proxy_args());
+
+        // 手动选择的格式优先，其次是音频模式，最后按站点规则与画质设置选择
+        if let Some(format) = &self.format {
+            args.push("-f".to_string());
+            args.push(format.clone());
+        } else if self.audio_only {
+            args.extend(["-f", "bestaudio/best"].map(String::from));
+        } else if let Some(format) = self
+            .site_rule
+            .as_ref()
+            .and_then(|r| r.format_for(&self.quality))
+        {
+            args.push("-f".to_string());
+            args.push(format.to_string());
+        }
+        if let Some(rule) = &self.site_rule {
+            args.extend(rule.build_args());
+        }
+
+        if self.audio_only {
+            args.extend([
+                "-x".to_string(),
+                "--audio-format".to_string(),
+                self.audio_format.clone(),
+                "--audio-quality".to_string(),
+                self.audio_quality.clone(),
+            ]);
+        }
+
+        args.extend(self.subtitles.build_args(self.audio_only));
+        args.extend(self.embed.build_args());
+        args.extend(self.clip.build_args());
+
+        args.push("-o".to_string());
+        args.push(self.output_template());
+        if self.archive_enabled() {
+            args.push("--download-archive".to_string());
+            args.push(archive::archive_path().to_string_lossy().to_string());
+        }
+        if self.download_playlist && !self.playlist_items.is_empty() {
+            args.push("--playlist-items".to_string());
+            args.push(self.playlist_items.clone());
+        }
+        if self.download_playlist && !self.playlist_ids.is_empty() {
+            args.push("--match-filters".to_string());
+            args.push(playlist::id_filter(&self.playlist_ids));
+        }
+        if self.download_playlist && self.playlist_reverse {
+            args.push("--playlist-reverse".to_string());
+        }
+        if self.split_chapters {
+            args.push("--split-chapters".to_string());
+            args.push("-o".to_string());
+            args.push(format!("chapter:{}", self.chapter_template()));
+        }
+        if !self.download_playlist {
+            args.push("--no-playlist".to_string());
+        }
+        args
+    }
+}
+
+/// 下载过程中产生的事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadEvent {
+    /// yt-dlp 输出的一行日志（stdout 与 stderr 合并）
+    Output(String),
+    /// 进度更新；下载进度行只产生该事件，不再作为日志输出
+    Progress(Progress),
+    /// yt-dlp 正在写入的文件，取消时据此清理临时文件
+    Destination(String),
+    /// 某个文件已下载并完成后处理，在 `Finished` 之前发送
+    Downloaded(DownloadedFile),
+    /// 进程已结束
+    Finished(DownloadOutcome),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadOutcome {
+    Success,
+    Failed(String),
+}
+
+/// 下载完成并移动到最终位置的文件
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct DownloadedFile {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    /// 站点，如 `Youtube`、`BiliBili`
+    pub extractor_key: Option<String>,
+    pub format: Option<String>,
+    pub filepath: Option<String>,
+    pub playlist_index: Option<u64>,
+    pub playlist_title: Option<String>,
+    /// 秒
+    pub duration: Option<f64>,
+    /// 由文件实际大小得出
+    #[serde(skip)]
+    pub size: Option<u64>,
+}
+
+impl DownloadedFile {
+    /// `--print-to-file` 使用的模板，每个文件输出一行 JSON
+    const TEMPLATE: &'static str =
+        "%(.{id,title,extractor_key,format,filepath,playlist_index,playlist_title,duration})j";
+
+    /// 读取 `--print-to-file` 写出的记录
+    fn read_records(path: &Path) -> Vec<Self> {
+        let data = std::fs::read_to_string(path).unwrap_or_default();
+        data.lines()
+            .filter_map(|line| serde_json::from_str::<Self>(line).ok())
+            .map(|mut file| {
+                file.size = file
+                    .filepath
+                    .as_ref()
+                    .and_then(|p| std::fs::metadata(p).ok())
+                    .map(|m| m.len());
+                file
+            })
+            .collect()
+    }
+}
+
+/// 释放到临时目录中的 yt-dlp 与 ffmpeg
+#[derive(Debug, Clone)]
+pub struct Binaries {
+    pub yt_dlp: PathBuf,
+    pub ffmpeg: PathBuf,
+}
+
+impl Binaries {
+    /// 把内置的二进制写入临时目录并设置可执行权限
+    pub fn install() -> Result<Self, String> {
+        let binaries = Self {
+            yt_dlp: temp_dir().join(YT_DLP_NAME),
+            ffmpeg: temp_dir().join(FFMPEG_NAME),
+        };
+        for (path, bytes) in binaries.files() {
+            write_executable(path, bytes)?;
+        }
+        Ok(binaries)
+    }
+
+    fn files(&self) -> [(&Path, &'static [u8]); 2] {
+        [(&self.yt_dlp, YT_DLP_BYTES), (&self.ffmpeg, FFMPEG_BYTES)]
+    }
+
+    /// 进程内只释放一次；多个任务并行时共用同一份文件，
+    /// 避免覆盖正在执行的二进制
+    pub fn shared() -> Result<Self, String> {
+        static INSTALLED: Mutex<Option<Binaries>> = Mutex::new(None);
+
+        let mut installed = INSTALLED.lock().unwrap();
+        if let Some(binaries) = installed.as_ref() {
+            // 临时目录可能被系统清理，只重新释放缺失的文件，不覆盖正在执行的那个
+            for (path, bytes) in binaries.files() {
+                if !path.exists() {
+                    write_executable(path, bytes)?;
+                }
+            }
+            return Ok(binaries.clone());
+        }
+        let binaries = Self::install()?;
+        *installed = Some(binaries.clone());
+        Ok(binaries)
+    }
+}
+
+fn write_executable(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    std::fs::write(path, bytes).map_err(|e| format!("写入临时文件失败: {}", e))?;
+
+    // 在 Unix 系统上设置可执行权限
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("设置可执行权限失败: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 正在运行的下载任务，通过 [`DownloadJob::recv`] 或迭代器读取事件，最后一个事件总是 [`DownloadEvent::Finished`]
+pub struct DownloadJob {
+    events: Receiver<DownloadEvent>,
+    handle: ProcessHandle,
+}
+
+impl DownloadJob {
+    /// 启动下载进程，立即返回
+    pub fn start(request: DownloadRequest) -> Result<Self, String> {
+        if request.url.trim().is_empty() {
+            return Err("请输入有效的视频链接".to_string());
+        }
+
+        static NEXT_RECORD: AtomicU64 = AtomicU64::new(0);
+        let record_path = temp_dir().join(format!(
+            "zdownload-{}-{}.jsonl",
+            std::process::id(),
+            NEXT_RECORD.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        let binaries = Binaries::shared()?;
+        let mut cmd = Command::new(&binaries.yt_dlp);
+        cmd.args(request.build_args(&binaries.ffmpeg, &record_path))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        process::isolate(&mut cmd);
+        let mut child = cmd.spawn().map_err(|e| format!("无法启动进程: {}", e))?;
+        let handle = ProcessHandle::new(child.id());
+
+        let (tx, rx) = mpsc::channel::<DownloadEvent>();
+
+        // 获取输出流
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        // 创建读取线程
+        let tx_stdout = tx.clone();
+        let stdout_thread = thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                forward_line(line.unwrap_or_default(), &tx_stdout);
+            }
+        });
+
+        let tx_stderr = tx.clone();
+        let stderr_thread = thread::spawn(move || {
+            for line in BufReader::new(stderr).lines() {
+                forward_line(line.unwrap_or_default(), &tx_stderr);
+            }
+        });
+
+        let waiter_handle = handle.clone();
+        thread::spawn(move || {
+            let status = child.wait();
+            waiter_handle.mark_exited();
+
+            // 加入线程
+            let _ = stdout_thread.join();
+            let _ = stderr_thread.join();
+
+            for file in DownloadedFile::read_records(&record_path) {
+                let _ = tx.send(DownloadEvent::Downloaded(file));
+            }
+            let _ = std::fs::remove_file(&record_path);
+
+            let outcome = match status {
+                Ok(status) if status.success() => DownloadOutcome::Success,
+                Ok(status) => DownloadOutcome::Failed(format!("线程退出状态：{}", status)),
+                Err(e) => DownloadOutcome::Failed(format!("等待进程失败: {}", e)),
+            };
+            let _ = tx.send(DownloadEvent::Finished(outcome));
+        });
+
+        Ok(Self { events: rx, handle })
+    }
+
+    /// 用于在其它线程中结束该任务
+    pub fn handle(&self) -> ProcessHandle {
+        self.handle.clone()
+    }
+
+    /// 阻塞等待下一个事件
+    pub fn recv(&self) -> Result<DownloadEvent, RecvError> {
+        self.events.recv()
+    }
+}
+
+/// 把一行输出转换成事件发送出去
+fn forward_line(line: String, tx: &Sender<DownloadEvent>) {
+    if let Some(destination) = progress::parse_destination(&line) {
+        let _ = tx.send(DownloadEvent::Destination(destination));
+    }
+
+    match progress::parse_line(&line) {
+        // JSON 进度行对用户没有阅读价值，只转成进度事件
+        Some(progress) if progress.stage == ProgressStage::Downloading || line.starts_with('{') => {
+            let _ = tx.send(DownloadEvent::Progress(progress));
+        }
+        Some(progress) => {
+            let _ = tx.send(DownloadEvent::Output(line));
+            let _ = tx.send(DownloadEvent::Progress(progress));
+        }
+        None => {
+            let _ = tx.send(DownloadEvent::Output(line));
+        }
+    }
+}
+
+impl Iterator for DownloadJob {
+    type Item = DownloadEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(template: &str) -> DownloadRequest {
+        DownloadRequest {
+            download_path: "/dl".to_string(),
+            filename_template: template.to_string(),
+            clip: ClipRange {
+                start: "1:00".to_string(),
+                end: "2:30".to_string(),
+                precise: false,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clip_suffix_in_output_template() {
+        assert_eq!(
+            request("").output_template(),
+            format!(
+                "/dl/{}",
+                template::DEFAULT_TEMPLATE.replace(".%(ext)s", " [01.00-02.30].%(ext)s")
+            )
+        );
+        assert_eq!(
+            request("%(uploader)s/%(title)s").output_template(),
+            "/dl/%(uploader)s/%(title)s [01.00-02.30]"
+        );
+    }
+
+    #[test]
+    fn subscription_jobs_always_use_archive() {
+        let args = |request: &DownloadRequest| {
+            request.build_args(Path::new("ffmpeg"), Path::new("record.jsonl"))
+        };
+        let audio = DownloadRequest {
+            url: "https://www.youtube.com/watch?v=abc".to_string(),
+            audio_only: true,
+            use_archive: true,
+            ..Default::default()
+        };
+        assert!(!args(&audio).contains(&"--download-archive".to_string()));
+
+        let subscription = DownloadRequest {
+            from_subscription: true,
+            ..audio
+        };
+        assert!(args(&subscription).contains(&"--download-archive".to_string()));
+    }
+
+    #[test]
+    fn clip_suffix_in_chapter_template() {
+        assert_eq!(
+            request("").chapter_template(),
+            "/dl/%(title)s [01.00-02.30]/%(section_number)02d - %(section_title)s.%(ext)s"
+        );
+        let whole = DownloadRequest {
+            clip: ClipRange::default(),
+            ..request("")
+        };
+        assert_eq!(
+            whole.chapter_template(),
+            "/dl/%(title)s/%(section_number)02d - %(section_title)s.%(ext)s"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! 下载存档：yt-dlp 的 `--download-archive` 文件，每行 `站点 视频ID`，
+//! 已记录的视频再次下载时会被跳过
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    /// 小写的站点名，如 `youtube`
+    pub extractor: String,
+    pub id: String,
+}
+
+impl ArchiveEntry {
+    /// 与 yt-dlp 一致，站点名使用小写的提取器名称
+    pub fn new(extractor: &str, id: &str) -> Self {
+        Self {
+            extractor: extractor.to_lowercase(),
+            id: id.to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let (extractor, id) = line.trim().split_once(' ')?;
+        Some(Self {
+            extractor: extractor.to_string(),
+            id: id.trim().to_string(),
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!("{} {}", self.extractor, self.id)
+    }
+}
+
+/// yt-dlp 因存档中已有而跳过某个视频时输出的日志
+pub fn is_skip_message(line: &str) -> bool {
+    line.contains("has already been recorded in the archive")
+}
+
+pub fn archive_path() -> PathBuf {
+    dirs_next::config_dir()
+        .unwrap_or_else(|| std::env::current_dir().unwrap())
+        .join("zdownload_archive.txt")
+}
+
+/// 读取全部记录，文件不存在时为空
+pub fn load() -> Vec<ArchiveEntry> {
+    load_from(&archive_path())
+}
+
+/// 无法解析的行会被忽略
+fn load_from(path: &Path) -> Vec<ArchiveEntry> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(ArchiveEntry::parse)
+        .collect()
+}
+
+/// 本进程内对存档文件的读写互斥。yt-dlp 进程只会追加记录，
+/// 重写文件由 [`DownloadQueue`](super::DownloadQueue) 保证没有使用存档的任务在运行
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// 先写入临时文件再替换，不会留下写了一半的存档
+fn rewrite(path: &Path, entries: &[ArchiveEntry]) -> Result<(), String> {
+    let temp = path.with_extension("txt.tmp");
+    let content: String = entries.iter().map(|e| e.to_line() + "\n").collect();
+    fs::write(&temp, content)
+        .and_then(|_| fs::rename(&temp, path))
+        .map_err(|e| format!("写入下载存档失败: {}", e))
+}
+
+/// 追加记录，已存在的跳过
+pub fn add(entries: &[ArchiveEntry]) -> Result<(), String> {
+    let _lock = LOCK.lock().unwrap();
+    add_to(&archive_path(), entries)
+}
+
+fn add_to(path: &Path, entries: &[ArchiveEntry]) -> Result<(), String> {
+    let mut existing = load_from(path);
+    let mut content = String::new();
+    for entry in entries {
+        if !existing.contains(entry) {
+            content.push_str(&(entry.to_line() + "\n"));
+            existing.push(entry.clone());
+        }
+    }
+    if content.is_empty() {
+        return Ok(());
+    }
+    // 最后一行没有换行时先补上，否则新记录会接在它后面
+    let needs_newline = fs::read(path).is_ok_and(|data| data.last().is_some_and(|&b| b != b'\n'));
+    if needs_newline {
+        content.insert(0, '\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| format!("写入下载存档失败: {}", e))
+}
+
+/// 删除一条记录，之后该视频可以重新下载；通过 [`DownloadQueue::remove_archive_entry`](super::DownloadQueue::remove_archive_entry) 调用
+pub(super) fn remove(entry: &ArchiveEntry) -> Result<(), String> {
+    let _lock = LOCK.lock().unwrap();
+    remove_from(&archive_path(), entry)
+}
+
+fn remove_from(path: &Path, entry: &ArchiveEntry) -> Result<(), String> {
+    let mut all = load_from(path);
+    all.retain(|e| e != entry);
+    rewrite(path, &all)
+}
+
+pub(super) fn clear() -> Result<(), String> {
+    let _lock = LOCK.lock().unwrap();
+    rewrite(&archive_path(), &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_archive(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "zdownload-archive-{}-{}.txt",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn entry(id: &str) -> ArchiveEntry {
+        ArchiveEntry::new("Youtube", id)
+    }
+
+    #[test]
+    fn parses_lines() {
+        assert_eq!(ArchiveEntry::parse("youtube abc"), Some(entry("abc")));
+        assert_eq!(
+            ArchiveEntry::parse("  youtube   abc \r"),
+            Some(entry("abc"))
+        );
+        assert_eq!(ArchiveEntry::parse("garbage"), None);
+        assert_eq!(ArchiveEntry::parse(""), None);
+        assert_eq!(entry("abc").to_line(), "youtube abc");
+    }
+
+    #[test]
+    fn recognises_skip_message() {
+        assert!(is_skip_message(
+            "[download] Rick Astley has already been recorded in the archive"
+        ));
+        assert!(!is_skip_message("[download] Destination: a.mp4"));
+    }
+
+    #[test]
+    fn appends_without_duplicates() {
+        let path = temp_archive("add");
+        assert!(load_from(&path).is_empty());
+
+        add_to(&path, &[entry("a"), entry("b"), entry("a")]).unwrap();
+        add_to(&path, &[entry("b"), entry("c")]).unwrap();
+        assert_eq!(load_from(&path), [entry("a"), entry("b"), entry("c")]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "youtube a\nyoutube b\nyoutube c\n"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tolerates_malformed_lines() {
+        let path = temp_archive("malformed");
+        fs::write(&path, "youtube a\n\ngarbage\nbilibili BV1").unwrap();
+        assert_eq!(
+            load_from(&path),
+            [entry("a"), ArchiveEntry::new("BiliBili", "BV1")]
+        );
+
+        // 最后一行缺少换行时不会和新记录连在一起
+        add_to(&path, &[entry("b")]).unwrap();
+        assert_eq!(
+            load_from(&path),
+            [entry("a"), ArchiveEntry::new("bilibili", "BV1"), entry("b")]
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn removes_and_clears() {
+        let path = temp_archive("remove");
+        fs::write(&path, "youtube a\ngarbage\nyoutube b\nyoutube a\n").unwrap();
+
+        remove_from(&path, &entry("a")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "youtube b\n");
+        assert!(!path.with_extension("txt.tmp").exists());
+
+        remove_from(&path, &entry("missing")).unwrap();
+        assert_eq!(load_from(&path), [entry("b")]);
+
+        rewrite(&path, &[]).unwrap();
+        assert!(load_from(&path).is_empty());
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+use super::progress::{format_duration, parse_duration};
+use serde::{Deserialize, Serialize};
+
+/// 只下载视频中的一段，起止时间为 `秒`、`分:秒` 或 `时:分:秒`，都为空表示下载整个视频
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ClipRange {
+    pub start: String,
+    /// 留空表示到视频结尾
+    pub end: String,
+    /// `--force-keyframes-at-cuts`：重新编码切点附近的画面，起止时间精确但更慢
+    pub precise: bool,
+}
+
+impl ClipRange {
+    pub fn is_empty(&self) -> bool {
+        self.start.trim().is_empty() && self.end.trim().is_empty()
+    }
+
+    /// 解析起止秒数，未设置时返回 `None`
+    pub fn parse(&self) -> Result<Option<(u64, Option<u64>)>, String> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let parse = |text: &str, name: &str| {
+            parse_duration(text.trim())
+                .ok_or_else(|| format!("{}格式不正确: {}", name, text.trim()))
+        };
+        let start = match self.start.trim() {
+            "" => 0,
+            text => parse(text, "开始时间")?,
+        };
+        let end = match self.end.trim() {
+            "" => None,
+            text => Some(parse(text, "结束时间")?),
+        };
+        if end.is_some_and(|end| end <= start) {
+            return Err("结束时间必须晚于开始时间".to_string());
+        }
+        Ok(Some((start, end)))
+    }
+
+    pub fn build_args(&self) -> Vec<String> {
+        let Ok(Some((start, end))) = self.parse() else {
+            return Vec::new();
+        };
+
+        let end = end.map_or("inf".to_string(), |e| e.to_string());
+        let mut args = vec![
+            "--download-sections".to_string(),
+            format!("*{}-{}", start, end),
+        ];
+        if self.precise {
+            args.push("--force-keyframes-at-cuts".to_string());
+        }
+        args
+    }
+
+    /// 加在文件名后的区间说明，如 ` [01.00-02.30]`；冒号在 Windows 文件名中不可用，换成点
+    pub fn file_suffix(&self) -> String {
+        let Ok(Some((start, end))) = self.parse() else {
+            return String::new();
+        };
+        let end = end.map_or("end".to_string(), format_duration);
+        format!(" [{}-{}]", format_duration(start), end).replace(':', ".")
+    }
+
+    /// 在输出模板的扩展名前加上区间说明，模板没有扩展名时加在末尾，
+    /// 同一视频的多个片段不会互相覆盖
+    pub fn apply_suffix(&self, template: &str) -> String {
+        let suffix = self.file_suffix();
+        let at = template
+            .rfind(".%(ext)s")
+            .or_else(|| template.rfind("%(ext)s"))
+            .unwrap_or(template.len());
+        format!("{}{}{}", &template[..at], suffix, &template[at..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(start: &str, end: &str) -> ClipRange {
+        ClipRange {
+            start: start.to_string(),
+            end: end.to_string(),
+            precise: false,
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(clip("", "").parse(), Ok(None));
+        assert_eq!(clip("1:00", "2:30").parse(), Ok(Some((60, Some(150)))));
+        assert_eq!(clip("", "90").parse(), Ok(Some((0, Some(90)))));
+        assert_eq!(clip("1:00:00", "").parse(), Ok(Some((3600, None))));
+        assert_eq!(clip(" 10 ", " 20 ").parse(), Ok(Some((10, Some(20)))));
+        assert!(clip("abc", "").parse().is_err());
+        assert!(clip("1:00", "x").parse().is_err());
+        assert!(clip("2:00", "1:00").parse().is_err());
+        assert!(clip("60", "1:00").parse().is_err());
+    }
+
+    #[test]
+    fn builds_section_args() {
+        assert!(clip("", "").build_args().is_empty());
+        assert_eq!(
+            clip("1:00", "2:30").build_args(),
+            ["--download-sections", "*60-150"]
+        );
+        let mut range = clip("10", "");
+        range.precise = true;
+        assert_eq!(
+            range.build_args(),
+            [
+                "--download-sections",
+                "*10-inf",
+                "--force-keyframes-at-cuts"
+            ]
+        );
+    }
+
+    #[test]
+    fn suffixes_file_names() {
+        assert_eq!(clip("", "").file_suffix(), "");
+        assert_eq!(clip("1:00", "2:30").file_suffix(), " [01.00-02.30]");
+        assert_eq!(clip("3723", "").file_suffix(), " [01.02.03-end]");
+
+        let range = clip("1:00", "2:30");
+        assert_eq!(
+            range.apply_suffix("%(title)s.%(ext)s"),
+            "%(title)s [01.00-02.30].%(ext)s"
+        );
+        assert_eq!(
+            range.apply_suffix("%(title)s - %(id)s"),
+            "%(title)s - %(id)s [01.00-02.30]"
+        );
+        assert_eq!(clip("", "").apply_suffix("%(title)s"), "%(title)s");
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// 下载完成后由 ffmpeg 写入输出文件的附加信息
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct EmbedOptions {
+    /// `--embed-thumbnail`，封面图
+    pub thumbnail: bool,
+    /// `--embed-metadata`，标题、作者、日期、简介等标签
+    pub metadata: bool,
+    /// `--embed-chapters`，章节标记
+    pub chapters: bool,
+}
+
+impl EmbedOptions {
+    pub fn build_args(&self) -> Vec<String> {
+        [
+            (self.thumbnail, "--embed-thumbnail"),
+            (self.metadata, "--embed-metadata"),
+            (self.chapters, "--embed-chapters"),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, flag)| flag.to_string())
+        .collect()
+    }
+}
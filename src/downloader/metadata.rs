@@ -0,0 +1,459 @@
+//! 通过 `yt-dlp -J` 在下载前获取视频信息
+use super::{Binaries, DownloadRequest};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::process::{Command, Stdio};
+
+/// `yt-dlp -J` 输出中用到的字段
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct VideoInfo {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub extractor_key: Option<String>,
+    /// 播放列表为 `playlist`，单个视频为 `video` 或缺省
+    #[serde(rename = "_type")]
+    pub kind: Option<String>,
+    pub thumbnail: Option<String>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub thumbnails: Vec<Thumbnail>,
+    pub uploader: Option<String>,
+    pub channel: Option<String>,
+    /// 秒
+    pub duration: Option<f64>,
+    /// `YYYYMMDD`
+    pub upload_date: Option<String>,
+    pub playlist_count: Option<u64>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub entries: Vec<PlaylistEntry>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub formats: Vec<FormatInfo>,
+    /// 语言代码 -> 可用的字幕文件
+    #[serde(deserialize_with = "null_as_default")]
+    pub subtitles: BTreeMap<String, Vec<SubtitleTrack>>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub automatic_captions: BTreeMap<String, Vec<SubtitleTrack>>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub chapters: Vec<Chapter>,
+}
+
+/// 视频中的章节标记
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Chapter {
+    pub title: Option<String>,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct SubtitleTrack {
+    pub ext: Option<String>,
+    /// 语言名称，如 `Chinese (Simplified)`
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Thumbnail {
+    pub url: String,
+}
+
+/// `--flat-playlist` 下播放列表中的一项
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct PlaylistEntry {
+    pub id: Option<String>,
+    /// 视频条目为 `url`，嵌套的子列表为 `playlist`
+    #[serde(rename = "_type")]
+    pub kind: Option<String>,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub duration: Option<f64>,
+    /// 处理该条目的提取器，如 `Youtube`，用于下载存档
+    pub ie_key: Option<String>,
+    /// 频道首页按标签页（视频、Shorts、直播）分成多个子列表，子列表的条目在这里
+    #[serde(deserialize_with = "null_as_default")]
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// 单个可下载格式
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct FormatInfo {
+    pub format_id: String,
+    pub ext: Option<String>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub resolution: Option<String>,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    pub fps: Option<f64>,
+    /// 总码率（kbit/s）
+    pub tbr: Option<f64>,
+    pub abr: Option<f64>,
+    pub filesize: Option<f64>,
+    pub filesize_approx: Option<f64>,
+    pub format_note: Option<String>,
+}
+
+impl FormatInfo {
+    fn has_codec(codec: &Option<String>) -> bool {
+        codec.as_deref().is_some_and(|c| c != "none")
+    }
+
+    pub fn has_video(&self) -> bool {
+        Self::has_codec(&self.vcodec)
+    }
+
+    pub fn has_audio(&self) -> bool {
+        Self::has_codec(&self.acodec)
+    }
+
+    pub fn size(&self) -> Option<f64> {
+        self.filesize.or(self.filesize_approx)
+    }
+
+    pub fn resolution_label(&self) -> String {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => format!("{}x{}", w, h),
+            (None, Some(h)) => format!("{}p", h),
+            _ => self.resolution.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl VideoInfo {
+    pub fn is_playlist(&self) -> bool {
+        self.kind.as_deref() == Some("playlist")
+    }
+
+    /// 条目中是否有子列表，如频道首页的各个标签页
+    pub fn is_nested(&self) -> bool {
+        self.entries.iter().any(|e| !e.entries.is_empty())
+    }
+
+    /// 播放列表中的视频数，优先使用 yt-dlp 报告的总数；
+    /// 嵌套列表报告的是子列表数，按展开后的视频计算
+    pub fn entry_count(&self) -> usize {
+        if self.is_nested() {
+            return self.video_entries().len();
+        }
+        self.playlist_count
+            .map(|c| c as usize)
+            .unwrap_or(self.entries.len())
+    }
+
+    /// 展开嵌套的子列表后的全部视频，同一视频只保留第一次出现
+    pub fn video_entries(&self) -> Vec<&PlaylistEntry> {
+        fn collect<'a>(entries: &'a [PlaylistEntry], videos: &mut Vec<&'a PlaylistEntry>) {
+            for entry in entries {
+                if !entry.entries.is_empty() {
+                    collect(&entry.entries, videos);
+                } else if entry.kind.as_deref() != Some("playlist")
+                    && !videos.iter().any(|v| v.id.is_some() && v.id == entry.id)
+                {
+                    videos.push(entry);
+                }
+            }
+        }
+
+        let mut videos = Vec::new();
+        collect(&self.entries, &mut videos);
+        videos
+    }
+
+    /// 封面地址，播放列表通常只有 `thumbnails`，取最后一张（分辨率最高）
+    pub fn thumbnail_url(&self) -> Option<String> {
+        self.thumbnail
+            .clone()
+            .or_else(|| self.thumbnails.last().map(|t| t.url.clone()))
+    }
+
+    /// 可下载的字幕语言：`(语言代码, 名称)`
+    pub fn subtitle_languages(&self) -> Vec<(String, String)> {
+        Self::languages(&self.subtitles)
+    }
+
+    pub fn automatic_caption_languages(&self) -> Vec<(String, String)> {
+        Self::languages(&self.automatic_captions)
+    }
+
+    fn languages(tracks: &BTreeMap<String, Vec<SubtitleTrack>>) -> Vec<(String, String)> {
+        tracks
+            .iter()
+            // `live_chat` 是直播聊天记录，不是字幕
+            .filter(|(code, _)| code.as_str() != "live_chat")
+            .map(|(code, list)| {
+                let name = list.iter().find_map(|t| t.name.clone());
+                (code.clone(), name.unwrap_or_else(|| code.clone()))
+            })
+            .collect()
+    }
+
+    /// 供文件名模板预览使用的字段值
+    pub fn template_fields(&self) -> BTreeMap<String, String> {
+        let best = self.video_formats().into_iter().next();
+        let mut fields = BTreeMap::new();
+        let mut insert = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                fields.insert(key.to_string(), value);
+            }
+        };
+
+        insert("id", self.id.clone());
+        insert("title", self.title.clone());
+        insert("uploader", self.uploader_name());
+        insert("upload_date", self.upload_date.clone());
+        insert("extractor", self.extractor_key.clone());
+        insert("resolution", best.as_ref().map(|f| f.resolution_label()));
+        insert("ext", best.and_then(|f| f.ext).or(Some("mp4".to_string())));
+        if self.is_playlist() {
+            insert("playlist", self.title.clone());
+            insert("playlist_title", self.title.clone());
+            insert("playlist_index", Some("1".to_string()));
+        }
+        fields
+    }
+
+    pub fn uploader_name(&self) -> Option<String> {
+        self.uploader.clone().or_else(|| self.channel.clone())
+    }
+
+    /// `20240102` 转成 `2024-01-02`
+    pub fn upload_date_label(&self) -> Option<String> {
+        let date = self.upload_date.as_deref()?;
+        if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(date.to_string());
+        }
+        Some(format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..]))
+    }
+
+    /// 含视频的格式，按分辨率、码率从高到低
+    pub fn video_formats(&self) -> Vec<FormatInfo> {
+        let mut formats: Vec<FormatInfo> = self
+            .formats
+            .iter()
+            .filter(|f| f.has_video())
+            .cloned()
+            .collect();
+        formats.sort_by(|a, b| {
+            (b.height, b.tbr.unwrap_or(0.0))
+                .partial_cmp(&(a.height, a.tbr.unwrap_or(0.0)))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        formats
+    }
+
+    /// 纯音频格式，按码率从高到低
+    pub fn audio_formats(&self) -> Vec<FormatInfo> {
+        let mut formats: Vec<FormatInfo> = self
+            .formats
+            .iter()
+            .filter(|f| f.has_audio() && !f.has_video())
+            .cloned()
+            .collect();
+        formats.sort_by(|a, b| {
+            b.abr
+                .or(b.tbr)
+                .partial_cmp(&a.abr.or(a.tbr))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        formats
+    }
+}
+
+/// 生成 `-f` 的格式选择表达式：视频与音频分别选择时合并下载
+pub fn format_selector(video: Option<&FormatInfo>, audio: Option<&FormatInfo>) -> Option<String> {
+    match (video, audio) {
+        (Some(v), Some(a)) if !v.has_audio() => Some(format!("{}+{}", v.format_id, a.format_id)),
+        (Some(v), _) => Some(v.format_id.clone()),
+        (None, Some(a)) => Some(a.format_id.clone()),
+        (None, None) => None,
+    }
+}
+
+/// yt-dlp 对缺失的列表字段输出 `null`（如没有章节时的 `chapters`），按空值处理
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// 运行 `yt-dlp -J` 获取视频信息，会阻塞直到进程结束
+pub fn fetch_info(request: &DownloadRequest) -> Result<VideoInfo, String> {
+    let binaries = Binaries::shared()?;
+    let output = Command::new(&binaries.yt_dlp)
+        .args(request.build_info_args())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("无法启动进程: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr
+            .lines()
+            .rev()
+            .find(|line| line.starts_with("ERROR:"))
+            .unwrap_or("获取视频信息失败");
+        return Err(message.to_string());
+    }
+
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("解析视频信息失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(id: &str, vcodec: &str, acodec: &str) -> FormatInfo {
+        FormatInfo {
+            format_id: id.to_string(),
+            vcodec: Some(vcodec.to_string()),
+            acodec: Some(acodec.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn entry(id: &str) -> PlaylistEntry {
+        PlaylistEntry {
+            id: Some(id.to_string()),
+            kind: Some("url".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn selects_formats() {
+        let video = format("137", "avc1", "none");
+        let muxed = format("22", "avc1", "mp4a");
+        let audio = format("140", "none", "mp4a");
+
+        assert_eq!(
+            format_selector(Some(&video), Some(&audio)).as_deref(),
+            Some("137+140")
+        );
+        // 已含音频的视频格式不再合并
+        assert_eq!(
+            format_selector(Some(&muxed), Some(&audio)).as_deref(),
+            Some("22")
+        );
+        assert_eq!(format_selector(Some(&video), None).as_deref(), Some("137"));
+        assert_eq!(format_selector(None, Some(&audio)).as_deref(), Some("140"));
+        assert_eq!(format_selector(None, None), None);
+    }
+
+    #[test]
+    fn sorts_video_formats() {
+        let info = VideoInfo {
+            formats: vec![
+                FormatInfo {
+                    height: Some(720),
+                    tbr: Some(1500.0),
+                    ..format("720-low", "avc1", "none")
+                },
+                FormatInfo {
+                    tbr: Some(130.0),
+                    ..format("audio", "none", "mp4a")
+                },
+                FormatInfo {
+                    height: Some(1080),
+                    ..format("1080", "vp9", "none")
+                },
+                FormatInfo {
+                    height: Some(720),
+                    tbr: Some(2500.0),
+                    ..format("720-high", "avc1", "mp4a")
+                },
+                format("storyboard", "none", "none"),
+            ],
+            ..Default::default()
+        };
+
+        let ids: Vec<String> = info
+            .video_formats()
+            .into_iter()
+            .map(|f| f.format_id)
+            .collect();
+        assert_eq!(ids, ["1080", "720-high", "720-low"]);
+    }
+
+    #[test]
+    fn sorts_audio_formats() {
+        let info = VideoInfo {
+            formats: vec![
+                FormatInfo {
+                    abr: Some(48.0),
+                    ..format("low", "none", "opus")
+                },
+                FormatInfo {
+                    tbr: Some(129.0),
+                    ..format("tbr-only", "none", "mp4a")
+                },
+                FormatInfo {
+                    abr: Some(160.0),
+                    ..format("high", "none", "opus")
+                },
+                FormatInfo {
+                    abr: Some(192.0),
+                    ..format("muxed", "avc1", "mp4a")
+                },
+            ],
+            ..Default::default()
+        };
+
+        let ids: Vec<String> = info
+            .audio_formats()
+            .into_iter()
+            .map(|f| f.format_id)
+            .collect();
+        assert_eq!(ids, ["high", "tbr-only", "low"]);
+    }
+
+    #[test]
+    fn flattens_nested_entries() {
+        let info = VideoInfo {
+            kind: Some("playlist".to_string()),
+            playlist_count: Some(2),
+            entries: vec![
+                PlaylistEntry {
+                    kind: Some("playlist".to_string()),
+                    entries: vec![entry("a"), entry("b")],
+                    ..Default::default()
+                },
+                PlaylistEntry {
+                    kind: Some("playlist".to_string()),
+                    entries: vec![entry("b"), entry("c")],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert!(info.is_nested());
+        let ids: Vec<_> = info
+            .video_entries()
+            .into_iter()
+            .filter_map(|e| e.id.as_deref())
+            .collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert_eq!(info.entry_count(), 3);
+    }
+
+    #[test]
+    fn counts_flat_entries() {
+        let info = VideoInfo {
+            kind: Some("playlist".to_string()),
+            playlist_count: Some(10),
+            entries: vec![entry("a"), entry("b")],
+            ..Default::default()
+        };
+
+        assert!(!info.is_nested());
+        assert_eq!(info.entry_count(), 10);
+    }
+}
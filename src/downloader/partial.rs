@@ -0,0 +1,119 @@
+//! 未下载完成时 yt-dlp 留下的临时文件
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// yt-dlp 临时文件的后缀
+const PARTIAL_SUFFIXES: [&str; 3] = [".part", ".ytdl", ".temp"];
+
+/// `name` 是否为 `file_name` 的临时文件：`a.mp4.part`、`a.mp4.part-Frag12`，
+/// 以及合并、后处理时产生的 `a.temp.mp4`
+fn is_partial_name(name: &str, file_name: &str) -> bool {
+    if let Some(rest) = name.strip_prefix(file_name) {
+        if PARTIAL_SUFFIXES.contains(&rest) || rest.starts_with(".part-Frag") {
+            return true;
+        }
+    }
+    match file_name.rsplit_once('.') {
+        Some((stem, ext)) => name == format!("{}.temp.{}", stem, ext),
+        None => false,
+    }
+}
+
+/// 找出与 `destinations` 中文件同名的临时文件，
+/// 如 `a.mp4.part`、`a.mp4.ytdl`、`a.mp4.part-Frag12`、`a.temp.mp4`
+pub fn find_partial_files(destinations: &[String]) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for destination in destinations {
+        let destination = Path::new(destination);
+        let (Some(dir), Some(file_name)) = (destination.parent(), destination.file_name()) else {
+            continue;
+        };
+        let file_name = file_name.to_string_lossy();
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if is_partial_name(&name, &file_name) {
+                let path = entry.path();
+                if !found.contains(&path) {
+                    found.push(path);
+                }
+            }
+        }
+    }
+    found
+}
+
+/// 删除临时文件，返回删除的数量
+pub fn remove_partial_files(destinations: &[String]) -> usize {
+    find_partial_files(destinations)
+        .into_iter()
+        .filter(|path| fs::remove_file(path).is_ok())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_partial_names() {
+        let file = "a.f137.mp4";
+        assert!(is_partial_name("a.f137.mp4.part", file));
+        assert!(is_partial_name("a.f137.mp4.ytdl", file));
+        assert!(is_partial_name("a.f137.mp4.temp", file));
+        assert!(is_partial_name("a.f137.mp4.part-Frag12", file));
+        assert!(is_partial_name("a.f137.mp4.part-Frag12.part", file));
+        assert!(is_partial_name("a.f137.temp.mp4", file));
+
+        assert!(!is_partial_name("a.f137.mp4", file));
+        assert!(!is_partial_name("a.f137.mp4.jpg", file));
+        assert!(!is_partial_name("a.f137.mp4 (2).part", file));
+        assert!(!is_partial_name("b.f137.mp4.part", file));
+        assert!(!is_partial_name("a.temp.mp4", file));
+    }
+
+    #[test]
+    fn finds_and_removes_partial_files() {
+        let dir = std::env::temp_dir().join(format!("zdownload-partial-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "a.mp4",
+            "a.mp4.part",
+            "a.mp4.part-Frag3",
+            "a.temp.mp4",
+            "a.f251.webm.part",
+            "b.mp4.part",
+        ] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let destinations = [
+            dir.join("a.mp4").to_string_lossy().to_string(),
+            dir.join("a.f251.webm").to_string_lossy().to_string(),
+        ];
+        let mut found: Vec<String> = find_partial_files(&destinations)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                "a.f251.webm.part",
+                "a.mp4.part",
+                "a.mp4.part-Frag3",
+                "a.temp.mp4"
+            ]
+        );
+
+        assert_eq!(remove_partial_files(&destinations), 4);
+        assert!(dir.join("a.mp4").exists());
+        assert!(dir.join("b.mp4.part").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
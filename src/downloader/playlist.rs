@@ -0,0 +1,299 @@
+//! 播放列表条目的选择：范围表达式与 `--playlist-items`，以及下载完成后生成 `.m3u8`
+use super::DownloadedFile;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// 解析 `1-10,15` 这样的范围，序号从 1 开始，超出 `count` 的部分报错
+pub fn parse_ranges(text: &str, count: usize) -> Result<BTreeSet<usize>, String> {
+    let mut items = BTreeSet::new();
+
+    for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|n| (1..=count).contains(n))
+                .ok_or_else(|| format!("序号无效: {}（共 {} 项）", s.trim(), count))
+        };
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start = if start.trim().is_empty() {
+                    1
+                } else {
+                    parse(start)?
+                };
+                let end = if end.trim().is_empty() {
+                    count
+                } else {
+                    parse(end)?
+                };
+                if start > end {
+                    return Err(format!("范围无效: {}", part));
+                }
+                items.extend(start..=end);
+            }
+            None => {
+                items.insert(parse(part)?);
+            }
+        }
+    }
+    Ok(items)
+}
+
+/// 把序号压缩成 `--playlist-items` 使用的范围，如 `1-3,7`
+pub fn format_ranges(items: &BTreeSet<usize>) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &item in items {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == item => *end = item,
+            _ => ranges.push((item, item)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 只保留这些视频的 `--match-filters` 表达式，如 `id~='^(?:a|b)$'`。
+/// 子列表本身不是单个视频，不受过滤影响
+pub fn id_filter(ids: &[String]) -> String {
+    let escape = |id: &String| {
+        id.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c.to_string()
+                } else {
+                    format!("\\{}", c)
+                }
+            })
+            .collect::<String>()
+    };
+    let ids: Vec<String> = ids.iter().map(escape).collect();
+    format!("id~='^(?:{})$'", ids.join("|"))
+}
+
+/// `.m3u8` 中的一项
+#[derive(Debug, Clone, PartialEq)]
+struct M3uEntry {
+    /// 相对于列表所在文件夹的文件名
+    name: String,
+    duration: i64,
+    title: String,
+    index: Option<u64>,
+}
+
+/// 为下载好的文件生成 `.m3u8`：按所在文件夹分组，每个文件夹一个列表，
+/// 以文件夹名命名，条目按播放列表序号排序，使用相对路径。
+/// 已有的列表会合并进来，再次下载时被存档跳过的视频不会从列表中消失
+pub fn write_m3u8(files: &[DownloadedFile]) -> Result<Vec<PathBuf>, String> {
+    let mut folders: BTreeMap<&Path, Vec<M3uEntry>> = BTreeMap::new();
+    for file in files {
+        let Some(path) = file.filepath.as_deref().map(Path::new) else {
+            continue;
+        };
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            continue;
+        };
+        let name = name.to_string_lossy().to_string();
+        let list = folders.entry(dir).or_default();
+        if !list.iter().any(|e| e.name == name) {
+            list.push(M3uEntry {
+                duration: file.duration.map_or(-1, |d| d.round() as i64),
+                title: file.title.clone().unwrap_or_else(|| name.clone()),
+                index: file.playlist_index.or_else(|| index_from_name(&name)),
+                name,
+            });
+        }
+    }
+
+    let mut written = Vec::new();
+    for (dir, new_entries) in folders {
+        let list_name = dir
+            .file_name()
+            .map_or("playlist".into(), |n| n.to_string_lossy());
+        let path = dir.join(format!("{}.m3u8", list_name));
+
+        // 保留旧列表中文件仍然存在、这次没有重新下载的条目
+        let mut entries: Vec<M3uEntry> = read_m3u8(&path)
+            .into_iter()
+            .filter(|e| dir.join(&e.name).exists())
+            .filter(|e| !new_entries.iter().any(|n| n.name == e.name))
+            .collect();
+        entries.extend(new_entries);
+        entries.sort_by_key(|e| e.index.unwrap_or(u64::MAX));
+
+        let mut content = String::from("#EXTM3U\n");
+        for entry in entries {
+            content.push_str(&format!(
+                "#EXTINF:{},{}\n{}\n",
+                entry.duration, entry.title, entry.name
+            ));
+        }
+        std::fs::write(&path, content).map_err(|e| format!("写入播放列表失败: {}", e))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// 读取已有的 `.m3u8`，不存在或无法读取时返回空列表
+fn read_m3u8(path: &Path) -> Vec<M3uEntry> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    let mut info = None;
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(rest) = line.strip_prefix("#EXTINF:") {
+            let (duration, title) = rest.split_once(',').unwrap_or((rest, ""));
+            info = Some((duration.trim().parse().unwrap_or(-1), title.to_string()));
+        } else if !line.starts_with('#') {
+            let (duration, title) = info.take().unwrap_or_else(|| (-1, line.to_string()));
+            entries.push(M3uEntry {
+                name: line.to_string(),
+                duration,
+                title,
+                index: index_from_name(line),
+            });
+        }
+    }
+    entries
+}
+
+/// 播放列表文件夹中的文件名以序号开头，如 `03 - 标题.mp4`
+fn index_from_name(name: &str) -> Option<u64> {
+    name.split_once(" - ")?.0.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(items: &[usize]) -> BTreeSet<usize> {
+        items.iter().copied().collect()
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_ranges("1-3,5", 10), Ok(set(&[1, 2, 3, 5])));
+        assert_eq!(parse_ranges(" 2 - 4 , 9 ", 10), Ok(set(&[2, 3, 4, 9])));
+        assert_eq!(parse_ranges("-2,9-", 10), Ok(set(&[1, 2, 9, 10])));
+        assert_eq!(parse_ranges("3,3,3", 10), Ok(set(&[3])));
+        assert_eq!(parse_ranges("1-5,3-7", 10), Ok(set(&[1, 2, 3, 4, 5, 6, 7])));
+        assert_eq!(parse_ranges("", 10), Ok(set(&[])));
+        assert_eq!(parse_ranges(",,", 10), Ok(set(&[])));
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        assert!(parse_ranges("5-3", 10).is_err());
+        assert!(parse_ranges("0", 10).is_err());
+        assert!(parse_ranges("0-3", 10).is_err());
+        assert!(parse_ranges("11", 10).is_err());
+        assert!(parse_ranges("1-11", 10).is_err());
+        assert!(parse_ranges("abc", 10).is_err());
+        assert!(parse_ranges("1-2-3", 10).is_err());
+        assert!(parse_ranges("1;2", 10).is_err());
+        assert!(parse_ranges("-1", 0).is_err());
+    }
+
+    #[test]
+    fn formats_ranges() {
+        assert_eq!(format_ranges(&set(&[])), "");
+        assert_eq!(format_ranges(&set(&[4])), "4");
+        assert_eq!(format_ranges(&set(&[1, 2, 3, 5])), "1-3,5");
+        assert_eq!(format_ranges(&set(&[1, 3, 5, 6, 7, 10])), "1,3,5-7,10");
+        let items = set(&[2, 3, 4, 8, 9, 12]);
+        assert_eq!(parse_ranges(&format_ranges(&items), 12), Ok(items));
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "zdownload-playlist-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn downloaded(dir: &Path, index: u64, title: &str) -> DownloadedFile {
+        let path = dir.join(format!("{:02} - {}.mp4", index, title));
+        std::fs::write(&path, b"").unwrap();
+        DownloadedFile {
+            title: Some(title.to_string()),
+            filepath: Some(path.to_string_lossy().to_string()),
+            playlist_index: Some(index),
+            duration: Some(61.4),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn writes_one_list_per_folder() {
+        let root = temp_dir("folders");
+        let a = root.join("列表 A");
+        let b = root.join("列表 B");
+        std::fs::create_dir_all(&a).unwrap();
+        std::fs::create_dir_all(&b).unwrap();
+
+        let files = [
+            downloaded(&a, 2, "第二"),
+            downloaded(&b, 1, "其它"),
+            downloaded(&a, 1, "第一"),
+        ];
+        let mut written = write_m3u8(&files).unwrap();
+        written.sort();
+        assert_eq!(written, [a.join("列表 A.m3u8"), b.join("列表 B.m3u8")]);
+        assert_eq!(
+            std::fs::read_to_string(a.join("列表 A.m3u8")).unwrap(),
+            "#EXTM3U\n#EXTINF:61,第一\n01 - 第一.mp4\n#EXTINF:61,第二\n02 - 第二.mp4\n"
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn merges_with_existing_list() {
+        let dir = temp_dir("merge");
+        let first = [downloaded(&dir, 1, "一"), downloaded(&dir, 3, "三")];
+        write_m3u8(&first).unwrap();
+
+        // 再次下载时 1、3 被存档跳过，只下载了新的 2；已删除的文件不再列出
+        std::fs::remove_file(dir.join("03 - 三.mp4")).unwrap();
+        let second = [downloaded(&dir, 2, "二")];
+        let written = write_m3u8(&second).unwrap();
+        let name = dir.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(written, [dir.join(format!("{}.m3u8", name))]);
+        assert_eq!(
+            std::fs::read_to_string(&written[0]).unwrap(),
+            "#EXTM3U\n#EXTINF:61,一\n01 - 一.mp4\n#EXTINF:61,二\n02 - 二.mp4\n"
+        );
+
+        // 重新下载同一文件时使用新的信息，不重复列出
+        let again = [DownloadedFile {
+            duration: Some(90.0),
+            ..downloaded(&dir, 2, "二")
+        }];
+        write_m3u8(&again).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&written[0]).unwrap(),
+            "#EXTM3U\n#EXTINF:61,一\n01 - 一.mp4\n#EXTINF:90,二\n02 - 二.mp4\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn builds_id_filter() {
+        let ids = ["dQw4w9WgXcQ".to_string(), "a-b_c".to_string()];
+        assert_eq!(id_filter(&ids), "id~='^(?:dQw4w9WgXcQ|a-b_c)$'");
+        assert_eq!(id_filter(&["a.b'c".to_string()]), r"id~='^(?:a\.b\'c)$'");
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// 让子进程成为独立的进程组组长，便于连同 ffmpeg 等子进程一起结束
+pub fn isolate(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// 用于结束正在运行的 yt-dlp 进程树
+#[derive(Debug, Clone)]
+pub struct ProcessHandle {
+    pid: u32,
+    exited: Arc<AtomicBool>,
+}
+
+impl ProcessHandle {
+    pub fn new(pid: u32) -> Self {
+        Self {
+            pid,
+            exited: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 进程退出后调用，避免误杀复用了同一 pid 的其它进程
+    pub fn mark_exited(&self) {
+        self.exited.store(true, Ordering::SeqCst);
+    }
+
+    /// 结束整个进程树（包括正在合并的 ffmpeg）
+    pub fn kill(&self) -> Result<(), String> {
+        if self.exited.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        let status = Command::new("kill")
+            .args(["-s", "TERM", "--", &format!("-{}", self.pid)])
+            .status();
+
+        #[cfg(windows)]
+        let status = Command::new("taskkill")
+            .args(["/PID", &self.pid.to_string(), "/T", "/F"])
+            .status();
+
+        match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("结束进程失败：{}", status)),
+            Err(e) => Err(format!("结束进程失败: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// 传给 yt-dlp 的 `--progress-template`，让下载与后处理进度都以单行 JSON 输出
+pub const PROGRESS_TEMPLATES: [&str; 2] = ["download:%(progress)j", "postprocess:%(progress)j"];
+
+static DOWNLOAD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+            ^\[download\]\s+
+            (?P<percent>[\d.]+)%                                  # 42.1%
+            (?:\s+of\s+~?\s*(?P<size>[\d.]+\s*[KMGTP]?i?B))?      # of ~120.50MiB
+            (?:\s+of\s+Unknown\ total\ size)?
+            (?:\s+in\s+[\d:]+)?                                   # in 00:00:37（完成时）
+            (?:\s+at\s+(?:(?P<speed>[\d.]+\s*[KMGTP]?i?B)/s|Unknown\ (?:B/s|speed)))?
+            (?:\s+ETA\s+(?:(?P<eta>[\d:]+)|Unknown))?
+        ",
+    )
+    .unwrap()
+});
+
+/// 任务当前所处的阶段
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum ProgressStage {
+    #[default]
+    Downloading,
+    /// ffmpeg 正在合并音视频
+    Merging,
+    /// 其它后处理步骤，保存 yt-dlp 输出的处理器名称，如 `ExtractAudio`
+    PostProcessing(String),
+}
+
+impl ProgressStage {
+    pub fn label(&self) -> String {
+        match self {
+            ProgressStage::Downloading => "下载中".to_string(),
+            ProgressStage::Merging => "合并音视频".to_string(),
+            ProgressStage::PostProcessing(name) => format!("后处理: {}", name),
+        }
+    }
+}
+
+/// 从 yt-dlp 输出解析出的结构化进度
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Progress {
+    pub stage: ProgressStage,
+    pub percent: Option<f64>,
+    pub total_bytes: Option<u64>,
+    /// 字节/秒
+    pub speed: Option<f64>,
+    /// 剩余秒数
+    pub eta: Option<u64>,
+}
+
+/// `--progress-template` 输出的进度对象，下载与后处理共用
+#[derive(Debug, Deserialize)]
+struct JsonProgress {
+    status: String,
+    /// 只有后处理进度才有
+    postprocessor: Option<String>,
+    downloaded_bytes: Option<f64>,
+    total_bytes: Option<f64>,
+    total_bytes_estimate: Option<f64>,
+    speed: Option<f64>,
+    eta: Option<f64>,
+    fragment_index: Option<u64>,
+    fragment_count: Option<u64>,
+}
+
+/// 解析 `--progress-template` 输出的 JSON 进度行
+pub fn parse_json(line: &str) -> Option<Progress> {
+    let json: JsonProgress = serde_json::from_str(line.trim()).ok()?;
+
+    if let Some(processor) = json.postprocessor {
+        let stage = match processor.as_str() {
+            "Merger" => ProgressStage::Merging,
+            _ => ProgressStage::PostProcessing(processor),
+        };
+        return Some(Progress {
+            stage,
+            percent: Some(if json.status == "finished" {
+                100.0
+            } else {
+                0.0
+            }),
+            ..Default::default()
+        });
+    }
+
+    let total = json.total_bytes.or(json.total_bytes_estimate);
+    let percent = if json.status == "finished" {
+        Some(100.0)
+    } else if let (Some(done), Some(total)) = (json.downloaded_bytes, total.filter(|t| *t > 0.0)) {
+        Some(done / total * 100.0)
+    } else if let (Some(index), Some(count)) =
+        (json.fragment_index, json.fragment_count.filter(|c| *c > 0))
+    {
+        Some(index as f64 / count as f64 * 100.0)
+    } else {
+        None
+    };
+
+    Some(Progress {
+        stage: ProgressStage::Downloading,
+        percent,
+        total_bytes: total.map(|t| t as u64),
+        speed: json.speed,
+        eta: json.eta.map(|e| e as u64),
+    })
+}
+
+/// 解析一行 yt-dlp 输出，非进度行返回 `None`。
+/// 优先按 JSON 进度解析，旧版 yt-dlp 不支持模板时退回到文本解析
+pub fn parse_line(line: &str) -> Option<Progress> {
+    let line = line.trim();
+
+    if line.starts_with('{') {
+        if let Some(progress) = parse_json(line) {
+            return Some(progress);
+        }
+    }
+
+    if let Some(caps) = DOWNLOAD_RE.captures(line) {
+        return Some(Progress {
+            stage: ProgressStage::Downloading,
+            percent: caps["percent"].parse().ok(),
+            total_bytes: caps
+                .name("size")
+                .and_then(|m| parse_size(m.as_str()))
+                .map(|b| b as u64),
+            speed: caps.name("speed").and_then(|m| parse_size(m.as_str())),
+            eta: caps.name("eta").and_then(|m| parse_duration(m.as_str())),
+        });
+    }
+
+    let processor = line.strip_prefix('[')?.split_once(']')?.0;
+    let stage = match processor {
+        "Merger" => ProgressStage::Merging,
+        "ExtractAudio"
+        | "VideoConvertor"
+        | "VideoRemuxer"
+        | "EmbedThumbnail"
+        | "EmbedSubtitle"
+        | "Metadata"
+        | "SubtitlesConvertor"
+        | "ThumbnailsConvertor"
+        | "SplitChapters"
+        | "ModifyChapters"
+        | "FixupM3u8"
+        | "FixupM4a"
+        | "FixupStretched"
+        | "FixupDuplicateMoov"
+        | "FixupTimestamp" => ProgressStage::PostProcessing(processor.to_string()),
+        _ => return None,
+    };
+
+    Some(Progress {
+        stage,
+        percent: Some(100.0),
+        ..Default::default()
+    })
+}
+
+/// 解析 yt-dlp 报告的目标文件，如 `[download] Destination: a.mp4`、
+/// `[Merger] Merging formats into "a.mp4"`
+pub fn parse_destination(line: &str) -> Option<String> {
+    let line = line.trim();
+    let (_, rest) = line.strip_prefix('[')?.split_once("] ")?;
+
+    if let Some(path) = rest.strip_prefix("Destination: ") {
+        return Some(path.to_string());
+    }
+    if let Some(path) = rest.strip_prefix("Merging formats into ") {
+        return Some(path.trim_matches('"').to_string());
+    }
+    rest.strip_suffix(" has already been downloaded")
+        .map(str::to_string)
+}
+
+/// `120.50MiB` / `3.2 KB` 转成字节数
+pub fn parse_size(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier = match unit.trim() {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024f64.powi(2),
+        "GiB" => 1024f64.powi(3),
+        "TiB" => 1024f64.powi(4),
+        "PiB" => 1024f64.powi(5),
+        "KB" | "kB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "PB" => 1e15,
+        _ => return None,
+    };
+    Some(number * multiplier)
+}
+
+/// `00:31` / `01:02:03` 转成秒
+pub fn parse_duration(text: &str) -> Option<u64> {
+    text.split(':')
+        .try_fold(0u64, |acc, part| Some(acc * 60 + part.parse::<u64>().ok()?))
+}
+
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2}{}", value, UNITS[unit])
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{:02}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_download_progress() {
+        let line = r#"{"status": "downloading", "downloaded_bytes": 15360, "total_bytes": 300000, "tmpfilename": "sample [sample].mp4.part", "filename": "sample [sample].mp4", "eta": 1, "speed": 200500.77941478536, "elapsed": 0.07749104499816895, "ctx_id": null, "_eta_str": "00:01", "_speed_str": " 195.80KiB/s", "_percent_str": "  5.1%", "_default_template": "  5.1% of  292.97KiB at  195.80KiB/s ETA 00:01"}"#;
+        let progress = parse_line(line).unwrap();
+        assert_eq!(progress.stage, ProgressStage::Downloading);
+        assert!((progress.percent.unwrap() - 5.12).abs() < 1e-9);
+        assert_eq!(progress.total_bytes, Some(300000));
+        assert_eq!(progress.speed.map(|s| s as u64), Some(200500));
+        assert_eq!(progress.eta, Some(1));
+    }
+
+    #[test]
+    fn parses_json_with_unknown_fields() {
+        // 直播或分片下载时总大小、速度与剩余时间都可能为 null
+        let line = r#"{"status": "downloading", "downloaded_bytes": 1024, "total_bytes": null, "total_bytes_estimate": null, "speed": null, "eta": null, "fragment_index": 3, "fragment_count": 12}"#;
+        let progress = parse_json(line).unwrap();
+        assert_eq!(progress.percent, Some(25.0));
+        assert_eq!(progress.total_bytes, None);
+        assert_eq!(progress.speed, None);
+        assert_eq!(progress.eta, None);
+
+        let line = r#"{"status": "downloading", "downloaded_bytes": 1024, "total_bytes_estimate": 4096.0}"#;
+        let progress = parse_json(line).unwrap();
+        assert_eq!(progress.percent, Some(25.0));
+        assert_eq!(progress.total_bytes, Some(4096));
+
+        let line = r#"{"status": "downloading", "downloaded_bytes": 1024}"#;
+        assert_eq!(parse_json(line).unwrap().percent, None);
+    }
+
+    #[test]
+    fn parses_json_finished_and_postprocess() {
+        let line = r#"{"status": "finished", "downloaded_bytes": 300000, "total_bytes": 300000, "filename": "a.mp4"}"#;
+        assert_eq!(parse_json(line).unwrap().percent, Some(100.0));
+
+        let line = r#"{"status": "started", "postprocessor": "Merger", "info_dict": {}}"#;
+        let progress = parse_json(line).unwrap();
+        assert_eq!(progress.stage, ProgressStage::Merging);
+        assert_eq!(progress.percent, Some(0.0));
+
+        let line = r#"{"status": "finished", "postprocessor": "ExtractAudio"}"#;
+        let progress = parse_json(line).unwrap();
+        assert_eq!(
+            progress.stage,
+            ProgressStage::PostProcessing("ExtractAudio".to_string())
+        );
+        assert_eq!(progress.percent, Some(100.0));
+
+        assert_eq!(parse_json("{not json"), None);
+    }
+
+    #[test]
+    fn parses_text_progress() {
+        let progress =
+            parse_line("[download]  45.3% of ~  12.34MiB at    1.50MiB/s ETA 00:05").unwrap();
+        assert_eq!(progress.percent, Some(45.3));
+        assert_eq!(progress.total_bytes, Some((12.34 * 1024.0 * 1024.0) as u64));
+        assert_eq!(progress.speed, Some(1.5 * 1024.0 * 1024.0));
+        assert_eq!(progress.eta, Some(5));
+
+        let progress =
+            parse_line("[download] 100% of   12.34MiB in 00:00:37 at 341.23KiB/s").unwrap();
+        assert_eq!(progress.percent, Some(100.0));
+        assert_eq!(progress.eta, None);
+    }
+
+    #[test]
+    fn parses_text_progress_with_unknown_values() {
+        let progress =
+            parse_line("[download]   2.0% of Unknown total size at Unknown B/s ETA Unknown")
+                .unwrap();
+        assert_eq!(progress.percent, Some(2.0));
+        assert_eq!(progress.total_bytes, None);
+        assert_eq!(progress.speed, None);
+        assert_eq!(progress.eta, None);
+
+        let progress =
+            parse_line("[download]  10.0% of ~ 5.00MiB at Unknown speed ETA Unknown").unwrap();
+        assert_eq!(progress.total_bytes, Some(5 * 1024 * 1024));
+        assert_eq!(progress.speed, None);
+    }
+
+    #[test]
+    fn parses_postprocess_lines() {
+        let progress = parse_line(r#"[Merger] Merging formats into "a.mp4""#).unwrap();
+        assert_eq!(progress.stage, ProgressStage::Merging);
+        let progress = parse_line("[ExtractAudio] Destination: a.mp3").unwrap();
+        assert_eq!(
+            progress.stage,
+            ProgressStage::PostProcessing("ExtractAudio".to_string())
+        );
+        assert_eq!(parse_line("[youtube] abc: Downloading webpage"), None);
+        assert_eq!(parse_line("[download] Destination: a.mp4"), None);
+        assert_eq!(parse_line("ERROR: unable to download"), None);
+    }
+
+    #[test]
+    fn parses_destinations() {
+        assert_eq!(
+            parse_destination("[download] Destination: sample [sample].mp4"),
+            Some("sample [sample].mp4".to_string())
+        );
+        assert_eq!(
+            parse_destination(r#"[Merger] Merging formats into "/tmp/a b.mp4""#),
+            Some("/tmp/a b.mp4".to_string())
+        );
+        assert_eq!(
+            parse_destination("[download] a.mp4 has already been downloaded"),
+            Some("a.mp4".to_string())
+        );
+        assert_eq!(
+            parse_destination("[ExtractAudio] Destination: a.mp3"),
+            Some("a.mp3".to_string())
+        );
+        assert_eq!(parse_destination("[download]  45.3% of 1.00MiB"), None);
+        assert_eq!(parse_destination("Destination: a.mp4"), None);
+    }
+
+    #[test]
+    fn parses_sizes_and_durations() {
+        assert_eq!(parse_size("120.50MiB"), Some(120.5 * 1024.0 * 1024.0));
+        assert_eq!(parse_size("3.2 KB"), Some(3200.0));
+        assert_eq!(parse_size("512B"), Some(512.0));
+        assert_eq!(parse_size("1GiB"), Some(1024f64.powi(3)));
+        assert_eq!(parse_size("NA"), None);
+        assert_eq!(parse_size("12XB"), None);
+
+        assert_eq!(parse_duration("00:31"), Some(31));
+        assert_eq!(parse_duration("01:02:03"), Some(3723));
+        assert_eq!(parse_duration("45"), Some(45));
+        assert_eq!(parse_duration("NA"), None);
+        assert_eq!(parse_duration("1::2"), None);
+    }
+
+    #[test]
+    fn formats_bytes_and_durations() {
+        assert_eq!(format_bytes(0.0), "0.00B");
+        assert_eq!(format_bytes(1023.0), "1023.00B");
+        assert_eq!(format_bytes(1536.0), "1.50KiB");
+        assert_eq!(format_bytes(5.0 * 1024.0 * 1024.0), "5.00MiB");
+        assert_eq!(format_bytes(2.0 * 1024f64.powi(5)), "2048.00TiB");
+
+        assert_eq!(format_duration(5), "00:05");
+        assert_eq!(format_duration(3723), "01:02:03");
+    }
+}
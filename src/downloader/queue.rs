@@ -0,0 +1,1120 @@
+use super::{
+    archive, partial, playlist, DownloadEvent, DownloadJob, DownloadOutcome, DownloadRequest,
+    DownloadedFile, ProcessHandle, Progress, TimeWindow,
+};
+use crate::db::{self, FinishedDownload, HistoryDb};
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, Weak},
+    thread,
+    time::Duration,
+};
+
+/// 每个任务最多保留的日志行数
+const MAX_LOG_LINES: usize = 500;
+
+pub type JobId = u64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum JobStatus {
+    #[default]
+    Queued,
+    Running,
+    Paused,
+    /// 下载时段结束时被暂停，时段开始后自动继续
+    WindowPaused,
+    Completed,
+    /// 全部视频都已在下载存档中，没有下载任何文件
+    Skipped,
+    Failed(String),
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "等待中",
+            JobStatus::Running => "下载中",
+            JobStatus::Paused => "已暂停",
+            JobStatus::WindowPaused => "等待时段",
+            JobStatus::Completed => "已完成",
+            JobStatus::Skipped => "已跳过",
+            JobStatus::Failed(_) => "失败",
+            JobStatus::Cancelled => "已取消",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Skipped | JobStatus::Failed(_) | JobStatus::Cancelled
+        )
+    }
+}
+
+/// 队列中的一个任务及其状态
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct QueuedJob {
+    pub id: JobId,
+    pub request: DownloadRequest,
+    pub status: JobStatus,
+    pub progress: Progress,
+    pub log: Vec<String>,
+    /// yt-dlp 写入过的文件
+    pub destinations: Vec<String>,
+    /// 已完成的文件
+    pub files: Vec<DownloadedFile>,
+    /// 对应的下载历史记录
+    pub history_id: Option<i64>,
+    /// 本次运行使用的限速，时段方案切换后据此判断是否需要重启
+    pub rate_limit: String,
+    /// 本次运行中因已在下载存档中而跳过的视频数
+    pub skipped: u32,
+    /// 失败后已自动重试的次数
+    pub attempts: u32,
+    /// 排队中的任务最早在该时间（Unix 秒）开始
+    pub next_run: Option<i64>,
+}
+
+impl QueuedJob {
+    /// 失败原因，优先取 yt-dlp 最后一条 `ERROR:` 输出
+    fn error_message(&self) -> Option<String> {
+        let JobStatus::Failed(e) = &self.status else {
+            return None;
+        };
+        self.log
+            .iter()
+            .rev()
+            .find(|line| line.starts_with("ERROR:"))
+            .cloned()
+            .or_else(|| Some(e.clone()))
+    }
+}
+
+struct QueueState {
+    jobs: Vec<QueuedJob>,
+    next_id: JobId,
+    max_concurrent: usize,
+    /// 只在该时段内下载，时段结束时暂停正在运行的任务
+    window: TimeWindow,
+    /// 每次状态变化都会自增，UI 据此判断是否需要刷新
+    version: u64,
+    /// 正在运行的任务的进程句柄
+    handles: HashMap<JobId, ProcessHandle>,
+    /// 已请求停止、等待进程退出的任务
+    stopping: HashMap<JobId, StopReason>,
+    /// 启动时恢复、等待用户决定是否继续的任务
+    restored: Vec<RestoredJob>,
+}
+
+/// 启动时从上次会话恢复的未完成任务
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoredJob {
+    pub id: JobId,
+    /// 下载目录中已存在的部分下载文件，继续时会断点续传
+    pub partial_files: Vec<PathBuf>,
+}
+
+/// 主动结束进程的原因，决定进程退出后任务进入什么状态
+#[derive(Debug, Clone, Copy)]
+enum StopReason {
+    Pause,
+    /// 下载时段结束
+    Window,
+    /// 限速时段切换，以新的 `--limit-rate` 重新启动
+    Restart,
+    Cancel {
+        remove_partials: bool,
+    },
+}
+
+/// 选出准备启动的任务：编号、本次运行的请求与下载历史记录
+type StartJob = (JobId, DownloadRequest, Option<i64>);
+
+/// 队列状态的变化都在这里完成，不涉及进程与界面，
+/// [`DownloadQueue`] 负责加锁、启动进程和持久化
+impl QueueState {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            jobs: Vec::new(),
+            next_id: 1,
+            max_concurrent: max_concurrent.max(1),
+            window: TimeWindow::default(),
+            version: 0,
+            handles: HashMap::new(),
+            stopping: HashMap::new(),
+            restored: Vec::new(),
+        }
+    }
+
+    fn job_mut(&mut self, id: JobId) -> Option<&mut QueuedJob> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    /// 加入队列，返回任务编号
+    fn push(&mut self, request: DownloadRequest) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(QueuedJob {
+            id,
+            next_run: request.start_at,
+            request,
+            ..Default::default()
+        });
+        self.version += 1;
+        id
+    }
+
+    /// 恢复上次保存的任务，重新编号并一律处于暂停状态
+    fn restore(&mut self, saved: Vec<QueuedJob>) {
+        for mut job in saved {
+            job.id = self.next_id;
+            self.next_id += 1;
+
+            let partial_files = partial::find_partial_files(&job.destinations);
+            if job.status != JobStatus::Paused {
+                job.status = JobStatus::Paused;
+                job.log.push("上次运行时未完成".to_string());
+            }
+            if !partial_files.is_empty() {
+                job.log.push(format!(
+                    "检测到 {} 个部分下载文件，继续时将断点续传",
+                    partial_files.len()
+                ));
+            }
+
+            self.restored.push(RestoredJob {
+                id: job.id,
+                partial_files,
+            });
+            self.jobs.push(job);
+        }
+    }
+
+    /// 需要保存的未结束任务
+    fn unfinished(&self) -> Vec<QueuedJob> {
+        self.jobs
+            .iter()
+            .filter(|job| !job.status.is_finished())
+            .cloned()
+            .collect()
+    }
+
+    fn has_pending(&self, url: &str) -> bool {
+        self.jobs
+            .iter()
+            .any(|job| job.request.url == url && !job.status.is_finished())
+    }
+
+    /// 已暂停的任务重新排队，返回是否有变化
+    fn resume(&mut self, id: JobId) -> bool {
+        let Some(job) = self.job_mut(id) else {
+            return false;
+        };
+        if job.status != JobStatus::Paused {
+            return false;
+        }
+        job.status = JobStatus::Queued;
+        job.log.push("继续下载".to_string());
+        self.version += 1;
+        true
+    }
+
+    /// 未运行的任务直接改变状态；正在运行的任务记下原因并结束进程，
+    /// 进程退出后由 [`QueueState::apply`] 决定最终状态
+    fn stop(&mut self, id: JobId, reason: StopReason) -> Result<(), String> {
+        self.version += 1;
+        let Some(job) = self.job_mut(id) else {
+            return Ok(());
+        };
+
+        match (&job.status, reason) {
+            (JobStatus::Queued | JobStatus::WindowPaused, StopReason::Pause) => {
+                job.status = JobStatus::Paused;
+                Ok(())
+            }
+            (
+                JobStatus::Queued | JobStatus::Paused | JobStatus::WindowPaused,
+                StopReason::Cancel { remove_partials },
+            ) => {
+                job.status = JobStatus::Cancelled;
+                if remove_partials {
+                    let removed = partial::remove_partial_files(&job.destinations);
+                    job.log.push(format!("已删除 {} 个临时文件", removed));
+                }
+                Ok(())
+            }
+            (JobStatus::Running, reason) => {
+                job.log.push(match reason {
+                    StopReason::Pause | StopReason::Window | StopReason::Restart => {
+                        "正在暂停...".to_string()
+                    }
+                    StopReason::Cancel { .. } => "正在取消...".to_string(),
+                });
+                self.stopping.insert(id, reason);
+                match self.handles.get(&id) {
+                    Some(handle) => handle.kill(),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn clear_finished(&mut self) {
+        self.jobs.retain(|job| !job.status.is_finished());
+        self.version += 1;
+    }
+
+    /// 按并发上限选出可以启动的任务并标记为运行中；
+    /// 同时处理下载时段结束与限速时段切换
+    fn take_startable(&mut self, now: DateTime<Local>) -> Vec<StartJob> {
+        let running = self
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Running)
+            .count();
+        let free = self.max_concurrent.saturating_sub(running);
+
+        let in_window = self.window.contains(now.time());
+        if !in_window {
+            self.pause_outside_window();
+        }
+        self.restart_on_rate_change(now.time());
+        let ids: Vec<JobId> = self
+            .jobs
+            .iter()
+            .filter(|job| matches!(job.status, JobStatus::Queued | JobStatus::WindowPaused))
+            .filter(|job| job.next_run.is_none_or(|at| at <= now.timestamp()))
+            .filter(|job| in_window || job.request.ignore_window)
+            .take(free)
+            .map(|job| job.id)
+            .collect();
+
+        ids.into_iter()
+            .filter_map(|id| {
+                let job = self.job_mut(id)?;
+                job.status = JobStatus::Running;
+                job.next_run = None;
+                job.skipped = 0;
+                // 固定本次运行的限速，避免启动时恰好跨过时段边界
+                job.rate_limit = job.request.rate_limit_at(now.time()).to_string();
+                let mut request = job.request.clone();
+                request.rate_limit_override = Some(job.rate_limit.clone());
+                Some((id, request, job.history_id))
+            })
+            .collect()
+    }
+
+    /// 进程没能启动。启动期间被暂停或取消的任务按请求处理，否则标记为失败
+    fn start_failed(&mut self, id: JobId, error: String) {
+        self.version += 1;
+        let stopped = self.stopping.remove(&id);
+        if let Some(job) = self.job_mut(id) {
+            job.status = match stopped {
+                Some(StopReason::Pause) => JobStatus::Paused,
+                Some(StopReason::Window) => JobStatus::WindowPaused,
+                Some(StopReason::Restart) => JobStatus::Queued,
+                Some(StopReason::Cancel { .. }) => JobStatus::Cancelled,
+                None => JobStatus::Failed(error),
+            };
+        }
+    }
+
+    /// 下载时段外暂停正在运行的任务，进程退出后进入 [`JobStatus::WindowPaused`]
+    fn pause_outside_window(&mut self) {
+        let ids: Vec<JobId> = self
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Running && !job.request.ignore_window)
+            .filter(|job| !self.stopping.contains_key(&job.id))
+            .map(|job| job.id)
+            .collect();
+        for id in ids {
+            self.stopping.insert(id, StopReason::Window);
+            if let Some(handle) = self.handles.get(&id) {
+                let _ = handle.kill();
+            }
+            if let Some(job) = self.job_mut(id) {
+                job.log.push("下载时段已结束，正在暂停...".to_string());
+            }
+            self.version += 1;
+        }
+    }
+
+    /// yt-dlp 启动后无法调整速度，限速时段切换时结束正在运行的任务并重新排队，
+    /// 重新启动时从 .part 文件续传并使用新的限速
+    fn restart_on_rate_change(&mut self, time: NaiveTime) {
+        let changed: Vec<(JobId, String)> = self
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Running)
+            .filter(|job| !self.stopping.contains_key(&job.id))
+            .filter(|job| job.request.rate_limit_at(time).trim() != job.rate_limit.trim())
+            .map(|job| (job.id, job.request.rate_limit_at(time).to_string()))
+            .collect();
+        for (id, limit) in changed {
+            self.stopping.insert(id, StopReason::Restart);
+            if let Some(handle) = self.handles.get(&id) {
+                let _ = handle.kill();
+            }
+            if let Some(job) = self.job_mut(id) {
+                let limit = match limit.trim() {
+                    "" => "不限速".to_string(),
+                    limit => format!("{}/s", limit),
+                };
+                job.log
+                    .push(format!("限速改为 {}，重新启动下载以应用新的限速", limit));
+            }
+            self.version += 1;
+        }
+    }
+
+    /// 把任务事件写回队列，`now` 用于计算自动重试的时间
+    fn apply(&mut self, id: JobId, event: DownloadEvent, now: i64) {
+        self.version += 1;
+
+        let stopped = match event {
+            DownloadEvent::Finished(_) => {
+                self.handles.remove(&id);
+                self.stopping.remove(&id)
+            }
+            _ => None,
+        };
+        let Some(job) = self.job_mut(id) else {
+            return;
+        };
+        match event {
+            DownloadEvent::Output(line) => {
+                if archive::is_skip_message(&line) {
+                    job.skipped += 1;
+                }
+                job.log.push(line);
+                if job.log.len() > MAX_LOG_LINES {
+                    let overflow = job.log.len() - MAX_LOG_LINES;
+                    job.log.drain(..overflow);
+                }
+            }
+            DownloadEvent::Progress(progress) => job.progress = progress,
+            DownloadEvent::Destination(path) => {
+                if !job.destinations.contains(&path) {
+                    job.destinations.push(path);
+                }
+            }
+            // 暂停或重启后再次运行时，已完成的文件会再报告一次
+            DownloadEvent::Downloaded(file) => {
+                if file.filepath.is_none() || !job.files.iter().any(|f| f.filepath == file.filepath)
+                {
+                    job.files.push(file);
+                }
+            }
+            DownloadEvent::Finished(outcome) => match (stopped, outcome) {
+                (Some(StopReason::Pause), _) => {
+                    job.status = JobStatus::Paused;
+                    job.log.push("已暂停".to_string());
+                }
+                (Some(StopReason::Restart), _) => {
+                    job.status = JobStatus::Queued;
+                    job.progress = Progress::default();
+                }
+                (Some(StopReason::Window), _) => {
+                    job.status = JobStatus::WindowPaused;
+                    job.log.push("已暂停，下载时段开始后自动继续".to_string());
+                }
+                (Some(StopReason::Cancel { remove_partials }), _) => {
+                    job.status = JobStatus::Cancelled;
+                    job.log.push("已取消".to_string());
+                    if remove_partials {
+                        let removed = partial::remove_partial_files(&job.destinations);
+                        job.log.push(format!("已删除 {} 个临时文件", removed));
+                    }
+                }
+                (None, DownloadOutcome::Success) if job.skipped > 0 && job.files.is_empty() => {
+                    job.status = JobStatus::Skipped;
+                    job.log.push("已在下载存档中，跳过下载".to_string());
+                }
+                (None, DownloadOutcome::Success) => {
+                    job.status = JobStatus::Completed;
+                    if job.skipped > 0 {
+                        job.log
+                            .push(format!("{} 个视频已在下载存档中，已跳过", job.skipped));
+                    }
+                    job.log.push("下载完成".to_string());
+                }
+                (None, DownloadOutcome::Failed(e)) => {
+                    job.log.push(e.clone());
+                    let policy = &job.request.retry;
+                    if job.attempts < policy.job_retries {
+                        job.attempts += 1;
+                        let delay = policy.job_delay(job.attempts);
+                        job.status = JobStatus::Queued;
+                        job.next_run = Some(now + delay.as_secs() as i64);
+                        job.progress = Progress::default();
+                        job.log.push(format!(
+                            "将在 {} 秒后进行第 {} 次重试",
+                            delay.as_secs(),
+                            job.attempts
+                        ));
+                    } else {
+                        job.status = JobStatus::Failed(e);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// 多任务下载队列，最多同时运行 `max_concurrent` 个任务，其余排队等待。
+/// 克隆得到的句柄共享同一个队列。
+#[derive(Clone)]
+pub struct DownloadQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+impl PartialEq for DownloadQueue {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl DownloadQueue {
+    pub fn new(max_concurrent: usize) -> Self {
+        let queue = Self {
+            state: Arc::new(Mutex::new(QueueState::new(max_concurrent))),
+        };
+        queue.spawn_ticker();
+        queue
+    }
+
+    /// 定时检查到点的任务（如等待重试的任务），队列被释放后自动退出
+    fn spawn_ticker(&self) {
+        let state: Weak<Mutex<QueueState>> = Arc::downgrade(&self.state);
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(1));
+            match state.upgrade() {
+                Some(state) => DownloadQueue { state }.schedule(),
+                None => break,
+            }
+        });
+    }
+
+    pub fn saved_jobs_path() -> PathBuf {
+        dirs_next::config_dir()
+            .unwrap_or_else(|| std::env::current_dir().unwrap())
+            .join("zdownload_queue.json")
+    }
+
+    /// 创建队列并恢复上次未完成的任务。恢复的任务一律处于暂停状态，
+    /// 由用户通过 [`DownloadQueue::resume_restored`] 决定是否继续
+    pub fn load(max_concurrent: usize) -> Self {
+        let queue = Self::new(max_concurrent);
+        let saved: Vec<QueuedJob> = fs::read_to_string(Self::saved_jobs_path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+
+        queue.state.lock().unwrap().restore(saved);
+        queue
+    }
+
+    /// 启动时恢复、尚未处理的任务
+    pub fn restored(&self) -> Vec<RestoredJob> {
+        self.state.lock().unwrap().restored.clone()
+    }
+
+    /// 继续所有恢复的任务
+    pub fn resume_restored(&self) {
+        for restored in self.take_restored() {
+            self.resume(restored.id);
+        }
+    }
+
+    /// 放弃所有恢复的任务
+    pub fn discard_restored(&self, remove_partials: bool) {
+        for restored in self.take_restored() {
+            let _ = self.cancel(restored.id, remove_partials);
+        }
+    }
+
+    /// 暂不处理，恢复的任务保留在队列中
+    pub fn dismiss_restored(&self) {
+        self.take_restored();
+    }
+
+    fn take_restored(&self) -> Vec<RestoredJob> {
+        let mut state = self.state.lock().unwrap();
+        state.version += 1;
+        std::mem::take(&mut state.restored)
+    }
+
+    /// 保存所有未结束的任务，应用重启或崩溃后据此恢复
+    fn persist(&self) {
+        let saved = self.state.lock().unwrap().unfinished();
+        if let Ok(json) = serde_json::to_string_pretty(&saved) {
+            let _ = fs::write(Self::saved_jobs_path(), json);
+        }
+    }
+
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        self.state.lock().unwrap().max_concurrent = max_concurrent.max(1);
+        self.schedule();
+    }
+
+    /// 加入队列，返回任务编号
+    pub fn enqueue(&self, request: DownloadRequest) -> JobId {
+        let id = self.state.lock().unwrap().push(request);
+        self.schedule();
+        self.persist();
+        id
+    }
+
+    /// 当前所有任务的快照
+    pub fn jobs(&self) -> Vec<QueuedJob> {
+        self.state.lock().unwrap().jobs.clone()
+    }
+
+    pub fn set_window(&self, window: TimeWindow) {
+        let mut state = self.state.lock().unwrap();
+        state.window = window;
+        state.version += 1;
+    }
+
+    /// 排队中的任务预计开始的时间（Unix 秒），综合定时、重试等待与下载时段；
+    /// 可以立即开始时为 `None`
+    pub fn next_start(&self, job: &QueuedJob) -> Option<i64> {
+        if !matches!(job.status, JobStatus::Queued | JobStatus::WindowPaused) {
+            return None;
+        }
+        let window = self.state.lock().unwrap().window.clone();
+        let now = Local::now();
+        let at = job.next_run.unwrap_or_default().max(now.timestamp());
+        let at = if job.request.ignore_window {
+            at
+        } else {
+            let at = Local.timestamp_opt(at, 0).single().unwrap_or(now);
+            window.next_open(at).timestamp()
+        };
+        Some(at).filter(|at| *at > now.timestamp())
+    }
+
+    /// 删除下载存档中的一条记录
+    pub fn remove_archive_entry(&self, entry: &archive::ArchiveEntry) -> Result<(), String> {
+        self.edit_archive(|| archive::remove(entry))
+    }
+
+    pub fn clear_archive(&self) -> Result<(), String> {
+        self.edit_archive(archive::clear)
+    }
+
+    /// 重写存档文件时 yt-dlp 追加的记录会丢失，所以只在没有使用存档的任务运行时进行；
+    /// 期间持有队列锁，不会有新任务启动
+    fn edit_archive(&self, edit: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+        let state = self.state.lock().unwrap();
+        let busy = state
+            .jobs
+            .iter()
+            .any(|job| job.status == JobStatus::Running && job.request.archive_enabled());
+        if busy {
+            return Err("有使用下载存档的任务正在运行，请等待下载结束后再修改".to_string());
+        }
+        edit()
+    }
+
+    /// 队列中是否已有该链接且尚未结束的任务
+    pub fn has_pending(&self, url: &str) -> bool {
+        self.state.lock().unwrap().has_pending(url)
+    }
+
+    pub fn version(&self) -> u64 {
+        self.state.lock().unwrap().version
+    }
+
+    /// 取消任务；正在运行的任务会结束整个进程树，
+    /// `remove_partials` 为真时在进程退出后删除残留的临时文件
+    pub fn cancel(&self, id: JobId, remove_partials: bool) -> Result<(), String> {
+        let result = self.stop(id, StopReason::Cancel { remove_partials });
+        self.persist();
+        result
+    }
+
+    /// 暂停任务，保留临时文件以便之后续传
+    pub fn pause(&self, id: JobId) -> Result<(), String> {
+        let result = self.stop(id, StopReason::Pause);
+        self.persist();
+        result
+    }
+
+    /// 继续已暂停的任务，yt-dlp 会从 .part 文件续传
+    pub fn resume(&self, id: JobId) {
+        if !self.state.lock().unwrap().resume(id) {
+            return;
+        }
+        self.persist();
+        self.schedule();
+    }
+
+    fn stop(&self, id: JobId, reason: StopReason) -> Result<(), String> {
+        self.state.lock().unwrap().stop(id, reason)
+    }
+
+    /// 移除已结束的任务
+    pub fn clear_finished(&self) {
+        self.state.lock().unwrap().clear_finished();
+        self.persist();
+    }
+
+    /// 按并发上限启动排队中的任务。选出的任务先标记为运行中，
+    /// 释放队列锁后再释放二进制、启动进程和写入历史，不阻塞界面与其它任务的事件
+    fn schedule(&self) {
+        let to_start = self.state.lock().unwrap().take_startable(Local::now());
+
+        for (id, request, history_id) in to_start {
+            let url = request.url.clone();
+            let result = DownloadJob::start(request);
+            let history_id = match (&result, history_id) {
+                (Ok(_), None) => HistoryDb::open()
+                    .and_then(|history| history.record_start(&url))
+                    .ok(),
+                _ => history_id,
+            };
+
+            let mut state = self.state.lock().unwrap();
+            match result {
+                Ok(download) => {
+                    state.version += 1;
+                    let handle = download.handle();
+                    // 启动期间被暂停或取消的任务，进程启动后立即结束，由 watch 处理后续状态
+                    if state.stopping.contains_key(&id) {
+                        let _ = handle.kill();
+                    }
+                    state.handles.insert(id, handle);
+                    if let Some(job) = state.job_mut(id) {
+                        job.history_id = history_id;
+                    }
+                    drop(state);
+                    self.watch(id, download);
+                }
+                Err(e) => state.start_failed(id, e),
+            }
+        }
+    }
+
+    /// 在后台线程中消费任务事件并写回队列
+    fn watch(&self, id: JobId, download: DownloadJob) {
+        let queue = self.clone();
+        thread::spawn(move || {
+            for event in download {
+                let finished = matches!(event, DownloadEvent::Finished(_));
+                let new_file = matches!(event, DownloadEvent::Destination(_));
+                queue.apply(id, event);
+                // 尽早记下正在写入的文件，崩溃后才能找回部分下载文件
+                if new_file {
+                    queue.persist();
+                }
+                if finished {
+                    queue.write_playlist_file(id);
+                    queue.record_history(id);
+                    queue.persist();
+                    queue.schedule();
+                    break;
+                }
+            }
+        });
+    }
+
+    /// 按播放列表文件夹下载完成后生成 `.m3u8`
+    fn write_playlist_file(&self, id: JobId) {
+        let Some(job) = self.jobs().into_iter().find(|job| job.id == id) else {
+            return;
+        };
+        if job.status != JobStatus::Completed
+            || !job.request.playlist_folder
+            || job.files.is_empty()
+        {
+            return;
+        }
+
+        let message = match playlist::write_m3u8(&job.files) {
+            Ok(paths) => paths
+                .iter()
+                .map(|p| format!("已生成播放列表 {}", p.display()))
+                .collect::<Vec<_>>(),
+            Err(e) => vec![e],
+        };
+        let mut state = self.state.lock().unwrap();
+        if let Some(job) = state.job_mut(id) {
+            job.log.extend(message);
+            state.version += 1;
+        }
+    }
+
+    /// 把任务结果写入下载历史
+    fn record_history(&self, id: JobId) {
+        let Some(job) = self.jobs().into_iter().find(|job| job.id == id) else {
+            return;
+        };
+        let Some(history_id) = job.history_id else {
+            return;
+        };
+
+        let status = match job.status {
+            JobStatus::Completed => db::STATUS_COMPLETED,
+            JobStatus::Skipped => db::STATUS_SKIPPED,
+            JobStatus::Failed(_) => db::STATUS_FAILED,
+            JobStatus::Cancelled => db::STATUS_CANCELLED,
+            JobStatus::Paused | JobStatus::WindowPaused => db::STATUS_PAUSED,
+            JobStatus::Queued | JobStatus::Running => return,
+        };
+        // 暂停后还会继续，文件等到最终结束时再写入
+        let files: Vec<FinishedDownload> =
+            if matches!(job.status, JobStatus::Paused | JobStatus::WindowPaused) {
+                Vec::new()
+            } else {
+                job.files
+                    .iter()
+                    .map(|file| FinishedDownload {
+                        title: file.title.clone(),
+                        site: file.extractor_key.clone(),
+                        output_path: file.filepath.clone(),
+                        size: file.size,
+                        format: file.format.clone(),
+                    })
+                    .collect()
+            };
+
+        if let Ok(mut history) = HistoryDb::open() {
+            let _ =
+                history.record_finish(history_id, status, job.error_message().as_deref(), &files);
+        }
+    }
+
+    fn apply(&self, id: JobId, event: DownloadEvent) {
+        let mut state = self.state.lock().unwrap();
+        state.apply(id, event, Local::now().timestamp());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{RateLimit, RateProfile, RetryPolicy};
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 1, 15, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn request(url: &str) -> DownloadRequest {
+        DownloadRequest {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn status(state: &QueueState, id: JobId) -> JobStatus {
+        state
+            .jobs
+            .iter()
+            .find(|j| j.id == id)
+            .unwrap()
+            .status
+            .clone()
+    }
+
+    fn finish(state: &mut QueueState, id: JobId, outcome: DownloadOutcome) {
+        state.apply(id, DownloadEvent::Finished(outcome), at(12, 0).timestamp());
+    }
+
+    #[test]
+    fn respects_concurrency_limit() {
+        let mut state = QueueState::new(2);
+        let ids: Vec<JobId> = (0..3)
+            .map(|i| state.push(request(&format!("https://a/{}", i))))
+            .collect();
+
+        let started: Vec<JobId> = state
+            .take_startable(at(12, 0))
+            .iter()
+            .map(|(id, ..)| *id)
+            .collect();
+        assert_eq!(started, ids[..2]);
+        assert!(state.take_startable(at(12, 0)).is_empty());
+        assert_eq!(status(&state, ids[2]), JobStatus::Queued);
+
+        finish(&mut state, ids[0], DownloadOutcome::Success);
+        assert_eq!(status(&state, ids[0]), JobStatus::Completed);
+        let started = state.take_startable(at(12, 0));
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].0, ids[2]);
+    }
+
+    #[test]
+    fn pauses_resumes_and_cancels() {
+        let mut state = QueueState::new(1);
+        let queued = state.push(request("https://a"));
+        state.stop(queued, StopReason::Pause).unwrap();
+        assert_eq!(status(&state, queued), JobStatus::Paused);
+        assert!(state.take_startable(at(12, 0)).is_empty());
+
+        assert!(state.resume(queued));
+        assert!(!state.resume(queued));
+        assert_eq!(status(&state, queued), JobStatus::Queued);
+
+        // 运行中的任务要等进程退出后才改变状态，退出结果不影响暂停
+        state.take_startable(at(12, 0));
+        state.stop(queued, StopReason::Pause).unwrap();
+        assert_eq!(status(&state, queued), JobStatus::Running);
+        finish(
+            &mut state,
+            queued,
+            DownloadOutcome::Failed("killed".to_string()),
+        );
+        assert_eq!(status(&state, queued), JobStatus::Paused);
+
+        state.resume(queued);
+        state.take_startable(at(12, 0));
+        state
+            .stop(
+                queued,
+                StopReason::Cancel {
+                    remove_partials: false,
+                },
+            )
+            .unwrap();
+        finish(&mut state, queued, DownloadOutcome::Success);
+        assert_eq!(status(&state, queued), JobStatus::Cancelled);
+        assert!(!state.resume(queued));
+
+        let other = state.push(request("https://b"));
+        state
+            .stop(
+                other,
+                StopReason::Cancel {
+                    remove_partials: false,
+                },
+            )
+            .unwrap();
+        assert_eq!(status(&state, other), JobStatus::Cancelled);
+
+        state.clear_finished();
+        assert!(state.jobs.is_empty());
+    }
+
+    #[test]
+    fn tracks_pending_urls() {
+        let mut state = QueueState::new(1);
+        let id = state.push(request("https://a"));
+        assert!(state.has_pending("https://a"));
+        assert!(!state.has_pending("https://b"));
+
+        state.take_startable(at(12, 0));
+        assert!(state.has_pending("https://a"));
+        finish(&mut state, id, DownloadOutcome::Success);
+        assert!(!state.has_pending("https://a"));
+    }
+
+    #[test]
+    fn start_failure_honours_pending_stop() {
+        let mut state = QueueState::new(2);
+        let paused = state.push(request("https://a"));
+        let failed = state.push(request("https://b"));
+        state.take_startable(at(12, 0));
+
+        state.stop(paused, StopReason::Pause).unwrap();
+        state.start_failed(paused, "无法启动".to_string());
+        state.start_failed(failed, "无法启动".to_string());
+        assert_eq!(status(&state, paused), JobStatus::Paused);
+        assert_eq!(
+            status(&state, failed),
+            JobStatus::Failed("无法启动".to_string())
+        );
+        assert!(state.stopping.is_empty());
+    }
+
+    #[test]
+    fn retries_failed_jobs_with_backoff() {
+        let mut state = QueueState::new(1);
+        let id = state.push(DownloadRequest {
+            retry: RetryPolicy {
+                job_retries: 1,
+                job_retry_delay: 30,
+                ..Default::default()
+            },
+            ..request("https://a")
+        });
+        state.take_startable(at(12, 0));
+        let now = at(12, 0).timestamp();
+        state.apply(
+            id,
+            DownloadEvent::Finished(DownloadOutcome::Failed("e".into())),
+            now,
+        );
+
+        let job = state.jobs[0].clone();
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.attempts, 1);
+        assert_eq!(job.next_run, Some(now + 30));
+
+        // 等待期间不会启动
+        assert!(state.take_startable(at(12, 0)).is_empty());
+        assert_eq!(state.take_startable(at(12, 1)).len(), 1);
+        assert_eq!(state.jobs[0].next_run, None);
+
+        state.apply(
+            id,
+            DownloadEvent::Finished(DownloadOutcome::Failed("e".into())),
+            now,
+        );
+        assert_eq!(status(&state, id), JobStatus::Failed("e".to_string()));
+    }
+
+    #[test]
+    fn pauses_outside_window_and_resumes_inside() {
+        let mut state = QueueState::new(3);
+        state.window = TimeWindow {
+            enabled: true,
+            start: "09:00".to_string(),
+            end: "18:00".to_string(),
+        };
+        let normal = state.push(request("https://a"));
+        let ignoring = state.push(DownloadRequest {
+            ignore_window: true,
+            ..request("https://b")
+        });
+        assert_eq!(state.take_startable(at(12, 0)).len(), 2);
+
+        // 时段结束：只暂停受时段限制的任务
+        assert!(state.take_startable(at(18, 30)).is_empty());
+        assert!(matches!(
+            state.stopping.get(&normal),
+            Some(StopReason::Window)
+        ));
+        assert!(!state.stopping.contains_key(&ignoring));
+        finish(
+            &mut state,
+            normal,
+            DownloadOutcome::Failed("killed".to_string()),
+        );
+        assert_eq!(status(&state, normal), JobStatus::WindowPaused);
+        assert!(state.take_startable(at(20, 0)).is_empty());
+
+        let started = state.take_startable(at(9, 0));
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].0, normal);
+    }
+
+    #[test]
+    fn restarts_when_rate_profile_changes() {
+        let mut state = QueueState::new(1);
+        let id = state.push(DownloadRequest {
+            rate_limit: RateLimit {
+                limit: String::new(),
+                profiles: vec![RateProfile::default()],
+            },
+            ..request("https://a")
+        });
+        let started = state.take_startable(at(10, 0));
+        assert_eq!(started[0].1.rate_limit_override.as_deref(), Some("2M"));
+        assert_eq!(state.jobs[0].rate_limit, "2M");
+
+        assert!(state.take_startable(at(17, 59)).is_empty());
+        assert!(state.stopping.is_empty());
+
+        state.take_startable(at(18, 0));
+        assert!(matches!(state.stopping.get(&id), Some(StopReason::Restart)));
+        finish(
+            &mut state,
+            id,
+            DownloadOutcome::Failed("killed".to_string()),
+        );
+        assert_eq!(status(&state, id), JobStatus::Queued);
+
+        let started = state.take_startable(at(18, 0));
+        assert_eq!(started[0].1.rate_limit_override.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn reports_archive_skips() {
+        let mut state = QueueState::new(1);
+        let id = state.push(request("https://a"));
+        state.take_startable(at(12, 0));
+        state.apply(
+            id,
+            DownloadEvent::Output(
+                "[download] abc: has already been recorded in the archive".to_string(),
+            ),
+            0,
+        );
+        finish(&mut state, id, DownloadOutcome::Success);
+        assert_eq!(status(&state, id), JobStatus::Skipped);
+
+        let id = state.push(request("https://b"));
+        state.take_startable(at(12, 0));
+        let file = DownloadedFile {
+            filepath: Some("/dl/a.mp4".to_string()),
+            ..Default::default()
+        };
+        state.apply(id, DownloadEvent::Downloaded(file.clone()), 0);
+        state.apply(id, DownloadEvent::Downloaded(file), 0);
+        finish(&mut state, id, DownloadOutcome::Success);
+        assert_eq!(status(&state, id), JobStatus::Completed);
+        assert_eq!(state.jobs[1].files.len(), 1);
+    }
+
+    #[test]
+    fn persists_and_restores_unfinished_jobs() {
+        let mut state = QueueState::new(2);
+        let done = state.push(request("https://done"));
+        let running = state.push(DownloadRequest {
+            audio_only: true,
+            ..request("https://running")
+        });
+        state.take_startable(at(12, 0));
+        finish(&mut state, done, DownloadOutcome::Success);
+        let paused = state.push(request("https://paused"));
+        state.stop(paused, StopReason::Pause).unwrap();
+        assert_eq!(status(&state, running), JobStatus::Running);
+
+        let json = serde_json::to_string(&state.unfinished()).unwrap();
+        let saved: Vec<QueuedJob> = serde_json::from_str(&json).unwrap();
+        assert_eq!(saved, state.unfinished());
+        assert_eq!(saved.len(), 2);
+
+        let mut restored = QueueState::new(2);
+        restored.push(request("https://existing"));
+        restored.restore(saved);
+        let urls: Vec<&str> = restored
+            .jobs
+            .iter()
+            .map(|j| j.request.url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            ["https://existing", "https://running", "https://paused"]
+        );
+        assert_eq!(
+            restored.jobs.iter().map(|j| j.id).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!(restored.jobs[1].request.audio_only);
+        assert!(restored.jobs[1..]
+            .iter()
+            .all(|j| j.status == JobStatus::Paused));
+        assert_eq!(restored.jobs[1].log.last().unwrap(), "上次运行时未完成");
+        assert_eq!(
+            restored.restored.iter().map(|r| r.id).collect::<Vec<_>>(),
+            [2, 3]
+        );
+        // 恢复的任务等用户决定，不会自动开始
+        let started = restored.take_startable(at(12, 0));
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].0, 1);
+    }
+}
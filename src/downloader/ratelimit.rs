@@ -0,0 +1,209 @@
+use super::schedule::TimeWindow;
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+/// 限速设置：默认限速，加上按时段生效的限速方案。
+/// yt-dlp 启动后无法调整速度，跨过时段边界时由队列重启正在运行的任务
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct RateLimit {
+    /// 不在任何时段内时的限速，如 `2M`、`500K`，为空表示不限速
+    pub limit: String,
+    /// 按顺序匹配，第一个包含当前时间的方案生效
+    pub profiles: Vec<RateProfile>,
+}
+
+/// 某个时段内的限速，如工作时间 09:00–18:00 限速 2M
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RateProfile {
+    pub enabled: bool,
+    /// `HH:MM`
+    pub start: String,
+    /// `HH:MM`，早于开始时间表示跨过午夜
+    pub end: String,
+    /// 为空表示这个时段不限速
+    pub limit: String,
+}
+
+impl Default for RateProfile {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            start: "09:00".to_string(),
+            end: "18:00".to_string(),
+            limit: "2M".to_string(),
+        }
+    }
+}
+
+impl RateProfile {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        let window = TimeWindow {
+            enabled: true,
+            start: self.start.clone(),
+            end: self.end.clone(),
+        };
+        self.enabled && window.contains(time)
+    }
+}
+
+/// 解析每秒字节数，与 yt-dlp 的 `--limit-rate` 一致：单位是字节，
+/// K、M、G 按 1024 换算（`2M`、`2MB`、`2MiB` 都是 2 MiB/s），不区分大小写。
+/// 小写 `b` 通常表示比特（如 `100Mb`），容易误解，直接报错。
+/// 为空或为 0 时返回 `None`，表示不限速
+pub fn parse_rate(text: &str) -> Result<Option<u64>, String> {
+    let text = text.trim();
+    let value = text.strip_suffix("/s").unwrap_or(text).trim_end();
+    if value.ends_with('b') {
+        return Err(format!(
+            "限速单位是字节，请使用 K、M（如 2M），不支持 Kb、Mb 等比特单位: {}",
+            text
+        ));
+    }
+    let value = value
+        .strip_suffix("iB")
+        .or_else(|| value.strip_suffix('B'))
+        .unwrap_or(value)
+        .trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| format!("限速格式不正确: {}", text))?;
+    let exponent = match unit.trim().to_ascii_uppercase().as_str() {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        _ => return Err(format!("限速格式不正确: {}", text)),
+    };
+    let bytes = (number * 1024f64.powi(exponent)) as u64;
+    Ok((bytes > 0).then_some(bytes))
+}
+
+impl RateLimit {
+    /// `time` 时刻生效的限速文本
+    pub fn current(&self, time: NaiveTime) -> &str {
+        self.profiles
+            .iter()
+            .find(|p| p.contains(time))
+            .map_or(&self.limit, |p| &p.limit)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        parse_rate(&self.limit)?;
+        for profile in &self.profiles {
+            parse_rate(&profile.limit)?;
+        }
+        Ok(())
+    }
+}
+
+/// `--limit-rate` 参数，无效的限速按不限速处理
+pub fn build_args(limit: &str) -> Vec<String> {
+    match parse_rate(limit) {
+        Ok(Some(bytes)) => vec!["--limit-rate".to_string(), bytes.to_string()],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn parses_rates() {
+        assert_eq!(parse_rate(""), Ok(None));
+        assert_eq!(parse_rate("0"), Ok(None));
+        assert_eq!(parse_rate("100"), Ok(Some(100)));
+        assert_eq!(parse_rate("500K"), Ok(Some(500 * 1024)));
+        assert_eq!(parse_rate("2M"), Ok(Some(2 * 1024 * 1024)));
+        assert_eq!(parse_rate("2 m"), Ok(Some(2 * 1024 * 1024)));
+        assert_eq!(parse_rate("2MB"), Ok(Some(2 * 1024 * 1024)));
+        assert_eq!(parse_rate("1.5MiB/s"), Ok(Some(1536 * 1024)));
+        assert_eq!(parse_rate("1G"), Ok(Some(1024 * 1024 * 1024)));
+        assert!(parse_rate("abc").is_err());
+        assert!(parse_rate("2X").is_err());
+        assert!(parse_rate("M").is_err());
+    }
+
+    #[test]
+    fn rejects_bit_units() {
+        assert!(parse_rate("100Mb").is_err());
+        assert!(parse_rate("500kb/s").is_err());
+        assert!(parse_rate("8b").is_err());
+        assert_eq!(parse_rate("500KB/s"), Ok(Some(500 * 1024)));
+        assert_eq!(parse_rate("100B"), Ok(Some(100)));
+    }
+
+    #[test]
+    fn builds_limit_rate_args() {
+        assert_eq!(build_args("2M"), ["--limit-rate", "2097152"]);
+        assert!(build_args("").is_empty());
+        assert!(build_args("bad").is_empty());
+    }
+
+    #[test]
+    fn picks_profile_by_time() {
+        let limit = RateLimit {
+            limit: "5M".to_string(),
+            profiles: vec![
+                RateProfile::default(),
+                RateProfile {
+                    start: "22:00".to_string(),
+                    end: "06:00".to_string(),
+                    limit: String::new(),
+                    ..Default::default()
+                },
+                RateProfile {
+                    enabled: false,
+                    start: "00:00".to_string(),
+                    end: "23:59".to_string(),
+                    limit: "1K".to_string(),
+                },
+            ],
+        };
+        assert_eq!(limit.current(time("10:00")), "2M");
+        assert_eq!(limit.current(time("09:00")), "2M");
+        assert_eq!(limit.current(time("18:00")), "5M");
+        assert_eq!(limit.current(time("20:00")), "5M");
+        // 跨过午夜的时段
+        assert_eq!(limit.current(time("23:30")), "");
+        assert_eq!(limit.current(time("03:00")), "");
+        assert_eq!(limit.current(time("06:00")), "5M");
+    }
+
+    #[test]
+    fn first_matching_profile_wins() {
+        let limit = RateLimit {
+            limit: String::new(),
+            profiles: vec![
+                RateProfile {
+                    limit: "1M".to_string(),
+                    ..Default::default()
+                },
+                RateProfile {
+                    start: "08:00".to_string(),
+                    end: "20:00".to_string(),
+                    limit: "3M".to_string(),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(limit.current(time("12:00")), "1M");
+        assert_eq!(limit.current(time("19:00")), "3M");
+        assert_eq!(limit.current(time("21:00")), "");
+        assert!(limit.validate().is_ok());
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 重试策略：前四项传给 yt-dlp 处理网络/分片层面的重试，
+/// 后两项是整个任务失败后由队列发起的重试
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// `--retries`
+    pub retries: u32,
+    /// `--fragment-retries`
+    pub fragment_retries: u32,
+    /// 两次重试之间的等待秒数，0 表示不等待
+    pub retry_sleep: u32,
+    /// 等待时间的增长方式：`fixed`、`linear` 或 `exp`
+    pub backoff: String,
+    /// 任务失败后自动重新下载的次数
+    pub job_retries: u32,
+    /// 任务重试的基础间隔（秒），第 n 次重试等待 `job_retry_delay * 2^(n-1)`
+    pub job_retry_delay: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 10,
+            fragment_retries: 10,
+            retry_sleep: 0,
+            backoff: "fixed".to_string(),
+            job_retries: 2,
+            job_retry_delay: 30,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn build_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--retries".to_string(),
+            self.retries.to_string(),
+            "--fragment-retries".to_string(),
+            self.fragment_retries.to_string(),
+        ];
+
+        if self.retry_sleep > 0 {
+            let expr = match self.backoff.as_str() {
+                "linear" => format!("linear={}", self.retry_sleep),
+                "exp" => format!("exp={}", self.retry_sleep),
+                _ => self.retry_sleep.to_string(),
+            };
+            for kind in ["http", "fragment"] {
+                args.push("--retry-sleep".to_string());
+                args.push(format!("{}:{}", kind, expr));
+            }
+        }
+        args
+    }
+
+    /// 第 `attempt` 次（从 1 开始）任务重试前的等待时间，指数退避
+    pub fn job_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_secs(self.job_retry_delay.saturating_mul(factor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_default_args() {
+        assert_eq!(
+            RetryPolicy::default().build_args(),
+            ["--retries", "10", "--fragment-retries", "10"]
+        );
+    }
+
+    #[test]
+    fn builds_retry_sleep_args() {
+        let mut policy = RetryPolicy {
+            retries: 3,
+            fragment_retries: 5,
+            retry_sleep: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.build_args(),
+            [
+                "--retries",
+                "3",
+                "--fragment-retries",
+                "5",
+                "--retry-sleep",
+                "http:2",
+                "--retry-sleep",
+                "fragment:2"
+            ]
+        );
+
+        policy.backoff = "linear".to_string();
+        assert_eq!(policy.build_args()[5], "http:linear=2");
+        policy.backoff = "exp".to_string();
+        assert_eq!(policy.build_args()[7], "fragment:exp=2");
+    }
+
+    #[test]
+    fn doubles_job_delay() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.job_delay(0), Duration::from_secs(30));
+        assert_eq!(policy.job_delay(1), Duration::from_secs(30));
+        assert_eq!(policy.job_delay(2), Duration::from_secs(60));
+        assert_eq!(policy.job_delay(4), Duration::from_secs(240));
+        assert_eq!(policy.job_delay(200), Duration::from_secs(u64::MAX));
+    }
+}
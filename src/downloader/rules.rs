@@ -0,0 +1,357 @@
+//! 按站点匹配的下载规则，取代原先写死的 YouTube 判断
+use super::proxy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 一条站点规则：按域名或正则匹配链接，提供该站点的格式、参数、请求头等
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct SiteRule {
+    pub name: String,
+    pub enabled: bool,
+    /// 匹配这些域名及其子域名
+    pub domains: Vec<String>,
+    /// 对完整链接匹配的正则，留空不使用
+    pub pattern: String,
+    /// 画质为“最佳”时的 `-f` 表达式，留空使用 yt-dlp 默认格式
+    pub format_best: String,
+    /// 画质为“普通”时的 `-f` 表达式
+    pub format_normal: String,
+    /// 附加的 yt-dlp 参数，按空白分隔，可用引号包含空格
+    pub extra_args: String,
+    /// `Name: Value` 形式的请求头
+    pub headers: Vec<String>,
+    /// 保存到下载目录下的子文件夹
+    pub subfolder: String,
+    /// 覆盖设置中的网络/分片重试次数
+    pub retries: Option<u32>,
+    /// 设置页中正在编辑的重试次数文本，校验通过后写入 `retries`，不保存
+    #[serde(skip)]
+    pub retries_text: Option<String>,
+    /// 覆盖设置中的代理，空字符串表示直连
+    pub proxy: Option<String>,
+}
+
+impl SiteRule {
+    /// 内置规则，最后一条匹配所有其它网站
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self {
+                name: "YouTube".to_string(),
+                enabled: true,
+                domains: vec!["youtube.com".to_string(), "youtu.be".to_string()],
+                ..Default::default()
+            },
+            Self {
+                name: "Bilibili".to_string(),
+                enabled: true,
+                domains: vec!["bilibili.com".to_string(), "b23.tv".to_string()],
+                format_best: "bestvideo+bestaudio/best".to_string(),
+                format_normal: "bestvideo[height<=720]+bestaudio/best[height<=720]/best"
+                    .to_string(),
+                headers: vec!["Referer: https://www.bilibili.com/".to_string()],
+                ..Default::default()
+            },
+            Self {
+                name: "其它网站".to_string(),
+                enabled: true,
+                pattern: ".*".to_string(),
+                format_best: "bestvideo[ext=mp4]+bestaudio[ext=m4a]/best[ext=mp4]/best".to_string(),
+                format_normal: "best[ext=mp4]".to_string(),
+                extra_args: "--merge-output-format mp4".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    /// 按顺序找到第一条启用且匹配的规则
+    pub fn find<'a>(rules: &'a [SiteRule], url: &str) -> Option<&'a SiteRule> {
+        rules.iter().find(|rule| rule.enabled && rule.matches(url))
+    }
+
+    pub fn matches(&self, url: &str) -> bool {
+        let host = host(url);
+        let domain_match = self.domains.iter().any(|domain| {
+            let domain = domain.trim().trim_start_matches('.').to_lowercase();
+            !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+        });
+        domain_match
+            || (!self.pattern.trim().is_empty()
+                && Regex::new(self.pattern.trim()).is_ok_and(|re| re.is_match(url)))
+    }
+
+    /// 规则中的错误，用于设置页提示
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.pattern.trim().is_empty() {
+            Regex::new(self.pattern.trim()).map_err(|e| format!("正则无效: {}", e))?;
+        }
+        if let Some(text) = self.retries_text.as_deref().map(str::trim) {
+            if !text.is_empty() && text.parse::<u32>().is_err() {
+                return Err(format!("重试次数无效: {}", text));
+            }
+        }
+        if let Some(header) = self.headers.iter().find(|h| !h.contains(':')) {
+            return Err(format!("请求头缺少冒号: {}", header));
+        }
+        split_args(&self.extra_args)?;
+        if self.subfolder.split(['/', '\\']).any(|part| part == "..") {
+            return Err("子文件夹不能包含 ..".to_string());
+        }
+        if let Some(url) = self.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+            proxy::parse(url)?;
+        }
+        Ok(())
+    }
+
+    pub fn format_for(&self, quality: &str) -> Option<&str> {
+        let format = match quality {
+            "best" => &self.format_best,
+            _ => &self.format_normal,
+        };
+        Some(format.trim()).filter(|f| !f.is_empty())
+    }
+
+    /// 请求头参数，获取视频信息时也需要
+    pub fn header_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for header in &self.headers {
+            if let Some((name, value)) = header.split_once(':') {
+                args.push("--add-headers".to_string());
+                args.push(format!("{}:{}", name.trim(), value.trim()));
+            }
+        }
+        args
+    }
+
+    /// 请求头与附加参数；格式与重试由调用方处理
+    pub fn build_args(&self) -> Vec<String> {
+        let mut args = self.header_args();
+        args.extend(split_args(&self.extra_args).unwrap_or_default());
+        args
+    }
+
+    /// 在输出模板前加上子文件夹
+    pub fn apply_subfolder(&self, template: &str) -> String {
+        match self.subfolder.trim().trim_matches(['/', '\\']) {
+            "" => template.to_string(),
+            folder => format!("{}/{}", folder, template),
+        }
+    }
+}
+
+/// 取出链接中的主机名，小写
+fn host(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or_default().to_lowercase()
+}
+
+/// 按空白拆分参数，支持单引号与双引号
+fn split_args(text: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_arg = false;
+
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("附加参数中的引号没有闭合".to_string());
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_domains_and_subdomains() {
+        let rules = SiteRule::defaults();
+        let youtube = &rules[0];
+        assert!(youtube.matches("https://www.youtube.com/watch?v=abc"));
+        assert!(youtube.matches("https://YOUTU.BE/abc"));
+        assert!(youtube.matches("youtube.com/watch?v=abc"));
+        assert!(youtube.matches("https://user@m.youtube.com:443/watch"));
+        assert!(!youtube.matches("https://notyoutube.com/watch"));
+        assert!(!youtube.matches("https://example.com/?u=youtube.com"));
+    }
+
+    #[test]
+    fn finds_first_enabled_rule() {
+        let mut rules = SiteRule::defaults();
+        assert_eq!(
+            SiteRule::find(&rules, "https://www.bilibili.com/video/BV1").map(|r| r.name.as_str()),
+            Some("Bilibili")
+        );
+        assert_eq!(
+            SiteRule::find(&rules, "https://vimeo.com/1").map(|r| r.name.as_str()),
+            Some("其它网站")
+        );
+
+        rules[1].enabled = false;
+        assert_eq!(
+            SiteRule::find(&rules, "https://www.bilibili.com/video/BV1").map(|r| r.name.as_str()),
+            Some("其它网站")
+        );
+        assert!(SiteRule::find(&rules[..2], "https://vimeo.com/1").is_none());
+    }
+
+    #[test]
+    fn matches_pattern() {
+        let rule = SiteRule {
+            pattern: r"^https://example\.com/video/\d+".to_string(),
+            ..Default::default()
+        };
+        assert!(rule.matches("https://example.com/video/42"));
+        assert!(!rule.matches("https://example.com/live/42"));
+
+        let invalid = SiteRule {
+            pattern: "(".to_string(),
+            ..Default::default()
+        };
+        assert!(!invalid.matches("https://example.com/"));
+    }
+
+    #[test]
+    fn validates_rules() {
+        for rule in SiteRule::defaults() {
+            assert!(rule.validate().is_ok(), "{}", rule.name);
+        }
+
+        let invalid = [
+            SiteRule {
+                pattern: "(".to_string(),
+                ..Default::default()
+            },
+            SiteRule {
+                headers: vec!["Referer".to_string()],
+                ..Default::default()
+            },
+            SiteRule {
+                extra_args: "--user-agent \"abc".to_string(),
+                ..Default::default()
+            },
+            SiteRule {
+                subfolder: "a/../b".to_string(),
+                ..Default::default()
+            },
+            SiteRule {
+                retries_text: Some("-1".to_string()),
+                ..Default::default()
+            },
+            SiteRule {
+                retries_text: Some("abc".to_string()),
+                ..Default::default()
+            },
+            SiteRule {
+                proxy: Some("ftp://127.0.0.1:21".to_string()),
+                ..Default::default()
+            },
+        ];
+        for rule in invalid {
+            assert!(rule.validate().is_err(), "{:?}", rule);
+        }
+
+        let direct = SiteRule {
+            proxy: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(direct.validate().is_ok());
+
+        for text in ["", " 5 "] {
+            let rule = SiteRule {
+                retries_text: Some(text.to_string()),
+                ..Default::default()
+            };
+            assert!(rule.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn splits_args() {
+        assert_eq!(
+            split_args(r#"--merge-output-format mp4  --user-agent "Mozilla 5.0" -o 'a b'"#)
+                .unwrap(),
+            [
+                "--merge-output-format",
+                "mp4",
+                "--user-agent",
+                "Mozilla 5.0",
+                "-o",
+                "a b"
+            ]
+        );
+        assert_eq!(split_args(r#"--referer """#).unwrap(), ["--referer", ""]);
+        assert!(split_args("  ").unwrap().is_empty());
+        assert!(split_args("'abc").is_err());
+    }
+
+    #[test]
+    fn builds_args() {
+        let rule = SiteRule {
+            headers: vec![
+                "Referer: https://www.bilibili.com/".to_string(),
+                "invalid".to_string(),
+            ],
+            extra_args: "--no-mtime".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            rule.header_args(),
+            ["--add-headers", "Referer:https://www.bilibili.com/"]
+        );
+        assert_eq!(
+            rule.build_args(),
+            [
+                "--add-headers",
+                "Referer:https://www.bilibili.com/",
+                "--no-mtime"
+            ]
+        );
+    }
+
+    #[test]
+    fn picks_format_and_subfolder() {
+        let rules = SiteRule::defaults();
+        assert_eq!(rules[0].format_for("best"), None);
+        assert_eq!(rules[2].format_for("normal"), Some("best[ext=mp4]"));
+
+        let rule = SiteRule {
+            subfolder: "/bilibili/".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            rule.apply_subfolder("%(title)s.%(ext)s"),
+            "bilibili/%(title)s.%(ext)s"
+        );
+        assert_eq!(
+            rules[0].apply_subfolder("%(title)s.%(ext)s"),
+            "%(title)s.%(ext)s"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! 下载时段：只在每天的某个时间段内下载，如 01:00–07:00
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TimeWindow {
+    pub enabled: bool,
+    /// `HH:MM`
+    pub start: String,
+    /// `HH:MM`，早于开始时间表示跨过午夜
+    pub end: String,
+}
+
+impl Default for TimeWindow {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "01:00".to_string(),
+            end: "07:00".to_string(),
+        }
+    }
+}
+
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").ok()
+}
+
+impl TimeWindow {
+    fn bounds(&self) -> Option<(NaiveTime, NaiveTime)> {
+        Some((parse_time(&self.start)?, parse_time(&self.end)?))
+    }
+
+    /// 未启用、时间无效或首尾相同时总是允许
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        let Some((start, end)) = self.bounds().filter(|_| self.enabled) else {
+            return true;
+        };
+        if start == end {
+            true
+        } else if start < end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+
+    /// 从 `now` 起最早可以开始下载的时间
+    pub fn next_open(&self, now: DateTime<Local>) -> DateTime<Local> {
+        if self.contains(now.time()) {
+            return now;
+        }
+        let Some((start, _)) = self.bounds() else {
+            return now;
+        };
+        let today = now.date_naive().and_time(start);
+        let next = if today > now.naive_local() {
+            today
+        } else {
+            today + Duration::days(1)
+        };
+        Local.from_local_datetime(&next).earliest().unwrap_or(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str) -> TimeWindow {
+        TimeWindow {
+            enabled: true,
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn time(text: &str) -> NaiveTime {
+        parse_time(text).unwrap()
+    }
+
+    fn at(day: u32, text: &str) -> DateTime<Local> {
+        let time = time(text);
+        Local
+            .from_local_datetime(
+                &chrono::NaiveDate::from_ymd_opt(2026, 1, day)
+                    .unwrap()
+                    .and_time(time),
+            )
+            .single()
+            .unwrap()
+    }
+
+    #[test]
+    fn contains_same_day_window() {
+        let window = window("09:00", "18:00");
+        assert!(!window.contains(time("08:59")));
+        assert!(window.contains(time("09:00")));
+        assert!(window.contains(time("17:59")));
+        assert!(!window.contains(time("18:00")));
+        assert!(!window.contains(time("23:00")));
+    }
+
+    #[test]
+    fn contains_window_across_midnight() {
+        let window = window("23:00", "06:00");
+        assert!(!window.contains(time("22:59")));
+        assert!(window.contains(time("23:00")));
+        assert!(window.contains(time("00:00")));
+        assert!(window.contains(time("05:59")));
+        assert!(!window.contains(time("06:00")));
+        assert!(!window.contains(time("12:00")));
+    }
+
+    #[test]
+    fn always_open_when_disabled_equal_or_invalid() {
+        assert!(window("10:00", "10:00").contains(time("03:00")));
+        assert!(window("abc", "06:00").contains(time("12:00")));
+        let disabled = TimeWindow {
+            enabled: false,
+            ..window("01:00", "02:00")
+        };
+        assert!(disabled.contains(time("12:00")));
+        assert_eq!(disabled.next_open(at(15, "12:00")), at(15, "12:00"));
+    }
+
+    #[test]
+    fn finds_next_open_time() {
+        let same_day = window("09:00", "18:00");
+        assert_eq!(same_day.next_open(at(15, "10:00")), at(15, "10:00"));
+        assert_eq!(same_day.next_open(at(15, "08:59")), at(15, "09:00"));
+        assert_eq!(same_day.next_open(at(15, "18:00")), at(16, "09:00"));
+
+        let overnight = window("23:00", "06:00");
+        assert_eq!(overnight.next_open(at(15, "06:00")), at(15, "23:00"));
+        assert_eq!(overnight.next_open(at(15, "22:59")), at(15, "23:00"));
+        assert_eq!(overnight.next_open(at(15, "23:30")), at(15, "23:30"));
+        assert_eq!(overnight.next_open(at(16, "05:59")), at(16, "05:59"));
+    }
+}
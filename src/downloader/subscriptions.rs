@@ -0,0 +1,257 @@
+//! 订阅同步：定期获取频道/播放列表的条目，把下载存档中没有的新视频加入队列
+use super::archive::{self, ArchiveEntry};
+use super::metadata::{self, VideoInfo};
+use super::{DownloadQueue, DownloadRequest};
+use crate::db::{HistoryDb, Settings, Subscription};
+use chrono::Local;
+use std::{sync::Mutex, thread, time::Duration};
+
+/// 后台线程检查是否有订阅到期的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 下载失败的视频最长隔多久再重新加入队列（秒）
+const MAX_RETRY_AFTER: i64 = 7 * 24 * 3600;
+
+/// 手动检查与后台检查不能同时进行，否则同一视频可能在加入队列前
+/// 同时通过 `has_pending` 检查而被加入两次
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+/// 站点规则没有为该画质指定格式时（如内置的 YouTube 规则）使用的格式
+fn quality_format(quality: &str) -> &'static str {
+    match quality {
+        "best" => "bestvideo*+bestaudio/best",
+        _ => "bestvideo*[height<=720]+bestaudio/best[height<=720]/best",
+    }
+}
+
+/// 订阅中每个视频的下载请求，应用订阅自己的画质与路径
+fn entry_request(subscription: &Subscription, url: &str, settings: &Settings) -> DownloadRequest {
+    let mut request = DownloadRequest::from_settings(url, settings);
+    if let Some(quality) = &subscription.quality {
+        request.quality = quality.clone();
+        // 订阅单独指定了画质，站点规则不区分画质时也要生效
+        let rule_format = request
+            .site_rule
+            .as_ref()
+            .and_then(|rule| rule.format_for(quality));
+        if rule_format.is_none() && !request.audio_only {
+            request.format = Some(quality_format(quality).to_string());
+        }
+    }
+    if let Some(path) = &subscription.download_path {
+        request.download_path = path.clone();
+    }
+    // 依赖存档去重，必须开启
+    request.use_archive = true;
+    request.from_subscription = true;
+    request.download_playlist = false;
+    request
+}
+
+/// 检查一个订阅，返回加入队列的视频数。
+/// 第一次检查只把现有视频写入存档，之后只下载新上传的视频
+pub fn sync(
+    subscription: &Subscription,
+    settings: &Settings,
+    queue: &DownloadQueue,
+) -> Result<usize, String> {
+    let mut request = DownloadRequest::from_settings(subscription.url.as_str(), settings);
+    request.download_playlist = true;
+    let info = metadata::fetch_info(&request)?;
+    if !info.is_playlist() {
+        return Err("该链接不是频道或播放列表".to_string());
+    }
+
+    let history = HistoryDb::open().map_err(|e| format!("打开数据库失败: {}", e))?;
+    if let Some(name) = info.title.as_deref().or(info.channel.as_deref()) {
+        history
+            .fill_subscription_name(subscription.id, name)
+            .map_err(|e| format!("保存订阅名称失败: {}", e))?;
+    }
+
+    let entries = archive_entries(&info);
+
+    if subscription.last_checked.is_none() {
+        let seeds: Vec<ArchiveEntry> = entries.into_iter().map(|(entry, _)| entry).collect();
+        archive::add(&seeds)?;
+        return Ok(0);
+    }
+
+    let archived = archive::load();
+    let interval = settings.subscription_interval.max(1) as i64 * 60;
+    let now = Local::now().timestamp();
+    // 频道按从新到旧排列，倒过来让旧的视频先下载
+    let mut added = 0;
+    for (entry, url) in entries.into_iter().rev() {
+        if archived.contains(&entry) || queue.has_pending(&url) {
+            continue;
+        }
+        // 下载失败过的视频按失败次数退避，不在每次检查时都重新下载
+        let (failures, last_failed) = history.failures(&url).unwrap_or_default();
+        if last_failed.is_some_and(|at| now - at < retry_after(interval, failures)) {
+            continue;
+        }
+        queue.enqueue(entry_request(subscription, &url, settings));
+        added += 1;
+    }
+    Ok(added)
+}
+
+/// 列表中每个视频的存档记录与链接，频道的各个标签页会被展开。
+/// 没有 `ie_key` 的条目无法得知 yt-dlp 写入存档时用的站点名，无法去重，跳过
+fn archive_entries(info: &VideoInfo) -> Vec<(ArchiveEntry, String)> {
+    info.video_entries()
+        .into_iter()
+        .filter_map(|entry| {
+            let id = entry.id.as_deref()?;
+            let extractor = entry.ie_key.as_deref()?;
+            let url = entry.url.clone()?;
+            Some((ArchiveEntry::new(extractor, id), url))
+        })
+        .collect()
+}
+
+/// 失败 `failures` 次后再次尝试前的等待秒数：订阅检查间隔的 2^(n-1) 倍，最长 7 天
+fn retry_after(interval: i64, failures: u32) -> i64 {
+    if failures == 0 {
+        return 0;
+    }
+    let factor = 2i64.saturating_pow(failures - 1);
+    interval.saturating_mul(factor).min(MAX_RETRY_AFTER)
+}
+
+/// 检查一个订阅并记录结果
+pub fn sync_and_record(
+    subscription: &Subscription,
+    queue: &DownloadQueue,
+) -> Result<usize, String> {
+    let _lock = SYNC_LOCK.lock().unwrap();
+    let result = sync(subscription, &Settings::load(), queue);
+    let history = HistoryDb::open().map_err(|e| format!("打开数据库失败: {}", e))?;
+    history
+        .mark_checked(subscription.id, result.as_ref().err().map(String::as_str))
+        .map_err(|e| format!("保存订阅状态失败: {}", e))?;
+    result
+}
+
+/// 程序运行期间在后台按设置的间隔检查所有启用的订阅
+pub fn spawn_sync(queue: DownloadQueue) {
+    thread::spawn(move || loop {
+        let interval = Settings::load().subscription_interval.max(1) as i64 * 60;
+        let now = Local::now().timestamp();
+        let subscriptions = HistoryDb::open()
+            .and_then(|history| history.subscriptions())
+            .unwrap_or_default();
+
+        for subscription in subscriptions {
+            let due = subscription
+                .last_checked
+                .is_none_or(|checked| now - checked >= interval);
+            if subscription.enabled && due {
+                let _ = sync_and_record(&subscription, &queue);
+            }
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn applies_quality_override() {
+        let settings = Settings::default();
+        let url = "https://www.youtube.com/watch?v=video000001";
+        let follow = Subscription::default();
+        assert_eq!(entry_request(&follow, url, &settings).format, None);
+
+        let normal = Subscription {
+            quality: Some("normal".to_string()),
+            ..Default::default()
+        };
+        let request = entry_request(&normal, url, &settings);
+        assert_eq!(request.format.as_deref(), Some(quality_format("normal")));
+        let args = request.build_args(Path::new("ffmpeg"), Path::new("record.jsonl"));
+        assert!(args.contains(&"--download-archive".to_string()));
+
+        let best = Subscription {
+            quality: Some("best".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            entry_request(&best, url, &settings).format.as_deref(),
+            Some(quality_format("best"))
+        );
+
+        // 站点规则有对应格式时使用规则的格式
+        let bilibili = entry_request(&best, "https://www.bilibili.com/video/BV1", &settings);
+        assert_eq!(bilibili.format, None);
+        assert_eq!(bilibili.quality, "best");
+
+        let audio = Settings {
+            download_mode: "audio".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(entry_request(&best, url, &audio).format, None);
+    }
+
+    #[test]
+    fn audio_subscription_records_archive() {
+        let settings = Settings {
+            download_mode: "audio".to_string(),
+            use_archive: "no".to_string(),
+            ..Default::default()
+        };
+        let subscription = Subscription {
+            url: "https://www.youtube.com/@channel".to_string(),
+            ..Default::default()
+        };
+        let request = entry_request(
+            &subscription,
+            "https://www.youtube.com/watch?v=video000001",
+            &settings,
+        );
+        assert!(request.audio_only);
+        let args = request.build_args(Path::new("ffmpeg"), Path::new("record.jsonl"));
+        assert!(args.contains(&"--download-archive".to_string()));
+    }
+
+    #[test]
+    fn expands_channel_tabs() {
+        let info: VideoInfo =
+            serde_json::from_str(include_str!("fixtures/channel_tabs.json")).unwrap();
+        let entries = archive_entries(&info);
+        let ids: Vec<&str> = entries.iter().map(|(e, _)| e.id.as_str()).collect();
+        assert_eq!(ids, ["video000003", "video000002", "short000001"]);
+        assert!(entries.iter().all(|(e, _)| e.extractor == "youtube"));
+        assert_eq!(
+            entries[2].1,
+            "https://www.youtube.com/shorts/short000001".to_string()
+        );
+    }
+
+    #[test]
+    fn flat_playlist_entries() {
+        let info: VideoInfo = serde_json::from_str(
+            r#"{"_type": "playlist", "extractor_key": "YoutubeTab", "entries": [
+                {"_type": "url", "ie_key": "Youtube", "id": "a", "url": "https://youtu.be/a"},
+                {"_type": "url", "id": "b", "url": "https://youtu.be/b"}
+            ]}"#,
+        )
+        .unwrap();
+        let entries = archive_entries(&info);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, ArchiveEntry::new("Youtube", "a"));
+        assert_eq!(entries[0].1, "https://youtu.be/a");
+    }
+
+    #[test]
+    fn failed_videos_back_off() {
+        assert_eq!(retry_after(3600, 0), 0);
+        assert_eq!(retry_after(3600, 1), 3600);
+        assert_eq!(retry_after(3600, 3), 4 * 3600);
+        assert_eq!(retry_after(3600, 40), MAX_RETRY_AFTER);
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+/// 字幕下载选项
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SubtitleOptions {
+    /// `--write-subs`，上传者提供的字幕
+    pub write_subs: bool,
+    /// `--write-auto-subs`，平台自动生成的字幕
+    pub write_auto_subs: bool,
+    /// `--sub-langs`，逗号分隔，支持正则，如 `zh.*,en`
+    pub languages: String,
+    /// `--convert-subs` 的目标格式：srt、ass、vtt，留空表示保持原格式
+    pub convert_format: String,
+    /// `--embed-subs`，把字幕封装进视频文件
+    pub embed: bool,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            write_subs: false,
+            write_auto_subs: false,
+            languages: "zh.*,en".to_string(),
+            convert_format: String::new(),
+            embed: false,
+        }
+    }
+}
+
+impl SubtitleOptions {
+    pub fn enabled(&self) -> bool {
+        self.write_subs || self.write_auto_subs
+    }
+
+    /// 拆分后的语言列表
+    pub fn language_list(&self) -> Vec<String> {
+        self.languages
+            .split(',')
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// 选中或取消某个语言，返回是否选中
+    pub fn toggle_language(&mut self, language: &str) -> bool {
+        let mut languages = self.language_list();
+        let selected = match languages.iter().position(|l| l == language) {
+            Some(index) => {
+                languages.remove(index);
+                false
+            }
+            None => {
+                languages.push(language.to_string());
+                true
+            }
+        };
+        self.languages = languages.join(",");
+        self.disable_if_empty();
+        selected
+    }
+
+    /// 没有选择任何语言时不再下载字幕，
+    /// 否则缺少 `--sub-langs` 的 yt-dlp 会按默认语言下载
+    pub fn disable_if_empty(&mut self) {
+        if self.language_list().is_empty() {
+            self.write_subs = false;
+            self.write_auto_subs = false;
+        }
+    }
+
+    /// 音频模式下不嵌入字幕，音频容器不支持。
+    /// 没有选择语言时不下载字幕，单独的 `--write-subs` 会让 yt-dlp 按默认语言下载
+    pub fn build_args(&self, audio_only: bool) -> Vec<String> {
+        let mut args = Vec::new();
+        let languages = self.language_list();
+        if !self.enabled() || languages.is_empty() {
+            return args;
+        }
+
+        if self.write_subs {
+            args.push("--write-subs".to_string());
+        }
+        if self.write_auto_subs {
+            args.push("--write-auto-subs".to_string());
+        }
+        args.push("--sub-langs".to_string());
+        args.push(languages.join(","));
+        if !self.convert_format.is_empty() {
+            args.push("--convert-subs".to_string());
+            args.push(self.convert_format.clone());
+        }
+        if self.embed && !audio_only {
+            args.push("--embed-subs".to_string());
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggles_languages() {
+        let mut options = SubtitleOptions {
+            languages: "en".to_string(),
+            ..Default::default()
+        };
+        assert!(options.toggle_language("zh-Hans"));
+        assert_eq!(options.languages, "en,zh-Hans");
+        assert!(!options.toggle_language("en"));
+        assert_eq!(options.languages, "zh-Hans");
+    }
+
+    #[test]
+    fn disables_when_selection_is_empty() {
+        let mut options = SubtitleOptions {
+            write_subs: true,
+            write_auto_subs: true,
+            languages: "en".to_string(),
+            ..Default::default()
+        };
+        assert!(!options.toggle_language("en"));
+        assert!(!options.enabled());
+        assert!(options.build_args(false).is_empty());
+    }
+
+    #[test]
+    fn no_args_without_languages() {
+        let mut options = SubtitleOptions {
+            write_subs: true,
+            write_auto_subs: true,
+            languages: " , ".to_string(),
+            embed: true,
+            ..Default::default()
+        };
+        assert!(options.build_args(false).is_empty());
+
+        options.languages = "zh.*,en".to_string();
+        assert_eq!(
+            options.build_args(true),
+            [
+                "--write-subs",
+                "--write-auto-subs",
+                "--sub-langs",
+                "zh.*,en"
+            ]
+        );
+    }
+}
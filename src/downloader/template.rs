@@ -0,0 +1,243 @@
+//! 输出文件名模板：校验与本地预览。
+//! 只实现 yt-dlp 模板语法中常用的部分（`%(field)s`、`%(field)03d`、`,` 备选字段、`&` 替换与 `|` 默认值），
+//! 预览结果可能与实际文件名略有出入
+use std::collections::BTreeMap;
+
+/// 默认模板，相对于下载目录
+pub const DEFAULT_TEMPLATE: &str = "%(title)s.%(ext)s";
+
+/// 模板编辑器中可插入的字段：`(字段, 说明, 插入的文本)`
+pub const FIELDS: [(&str, &str, &str); 9] = [
+    ("title", "标题", "%(title)s"),
+    ("uploader", "作者", "%(uploader)s"),
+    ("upload_date", "上传日期", "%(upload_date)s"),
+    ("id", "视频 ID", "%(id)s"),
+    ("playlist", "播放列表", "%(playlist)s"),
+    ("playlist_index", "列表序号", "%(playlist_index)03d"),
+    ("resolution", "分辨率", "%(resolution)s"),
+    ("extractor", "站点", "%(extractor)s"),
+    ("ext", "扩展名", "%(ext)s"),
+];
+
+/// 模板中的一个 `%(...)X` 占位符
+struct Placeholder<'a> {
+    /// 括号内的原文，如 `playlist_index` 或 `playlist_title&{}/|`
+    expr: &'a str,
+    /// 括号后的格式说明，如 `s`、`03d`
+    spec: &'a str,
+    /// 占位符在模板中的字节范围
+    range: std::ops::Range<usize>,
+}
+
+/// 按顺序解析出所有占位符，语法错误时返回说明
+fn placeholders(template: &str) -> Result<Vec<Placeholder<'_>>, String> {
+    let mut result = Vec::new();
+    let mut rest = 0;
+
+    while let Some(offset) = template[rest..].find("%(") {
+        let start = rest + offset;
+        let expr_start = start + 2;
+
+        // 括号可以嵌套，如 `%(title)s` 外层不会出现，但默认值里可能包含括号
+        let mut depth = 1;
+        let mut expr_end = None;
+        for (i, c) in template[expr_start..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        expr_end = Some(expr_start + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let expr_end =
+            expr_end.ok_or_else(|| format!("第 {} 个字符处的括号没有闭合", start + 1))?;
+
+        let spec_start = expr_end + 1;
+        let spec_len = template[spec_start..]
+            .find(|c: char| c.is_ascii_alphabetic())
+            .filter(|i| {
+                template[spec_start..spec_start + i]
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || "#0-+ .".contains(c))
+            })
+            .ok_or_else(|| {
+                format!(
+                    "%({}) 后缺少格式类型，如 s 或 d",
+                    &template[expr_start..expr_end]
+                )
+            })?;
+        let spec_end = spec_start + spec_len + 1;
+
+        let expr = &template[expr_start..expr_end];
+        if expr.trim().is_empty() {
+            return Err("存在空的字段 %()".to_string());
+        }
+
+        result.push(Placeholder {
+            expr,
+            spec: &template[spec_start..spec_end],
+            range: start..spec_end,
+        });
+        rest = spec_end;
+    }
+    Ok(result)
+}
+
+/// 保存前校验模板
+pub fn validate(template: &str) -> Result<(), String> {
+    let template = template.trim();
+    if template.is_empty() {
+        return Err("模板不能为空".to_string());
+    }
+    if template.starts_with(['/', '\\']) || template.chars().nth(1) == Some(':') {
+        return Err("模板应为下载目录中的相对路径".to_string());
+    }
+    if template.split(['/', '\\']).any(|part| part == "..") {
+        return Err("模板不能包含 ..".to_string());
+    }
+
+    let placeholders = placeholders(template)?;
+    if !placeholders.iter().any(|p| p.expr == "ext") {
+        return Err("模板必须包含 %(ext)s，否则文件没有扩展名".to_string());
+    }
+    Ok(())
+}
+
+/// 用给定的字段值渲染模板，缺失的字段显示为 `NA`，与 yt-dlp 一致
+pub fn render(template: &str, fields: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut output = String::new();
+    let mut last = 0;
+
+    for placeholder in placeholders(template)? {
+        output.push_str(&template[last..placeholder.range.start]);
+        output.push_str(&render_placeholder(&placeholder, fields));
+        last = placeholder.range.end;
+    }
+    output.push_str(&template[last..]);
+    Ok(output)
+}
+
+fn render_placeholder(placeholder: &Placeholder, fields: &BTreeMap<String, String>) -> String {
+    // `field&替换|默认值`：有值时输出替换文本（`{}` 代表字段值），否则输出默认值
+    let (expr, default) = match placeholder.expr.split_once('|') {
+        Some((expr, default)) => (expr, Some(default)),
+        None => (placeholder.expr, None),
+    };
+    let (name, replacement) = match expr.split_once('&') {
+        Some((name, replacement)) => (name, Some(replacement)),
+        None => (expr, None),
+    };
+
+    // `a,b`：依次尝试各个字段，取第一个有值的
+    let Some(value) = name
+        .split(',')
+        .find_map(|name| fields.get(name.trim()).filter(|v| !v.is_empty()))
+    else {
+        return default.unwrap_or("NA").to_string();
+    };
+    let value = format_value(value, placeholder.spec);
+    match replacement {
+        Some(replacement) => replacement.replace("{}", &value),
+        None => value,
+    }
+}
+
+/// 目前只处理整数的补零宽度（`03d`），其它格式按原样输出
+fn format_value(value: &str, spec: &str) -> String {
+    if let Some(width) = spec.strip_suffix('d') {
+        if let (Ok(number), Ok(width)) = (value.parse::<i64>(), width.parse::<usize>()) {
+            return if width > 0 && spec.starts_with('0') {
+                format!("{:0width$}", number, width = width)
+            } else {
+                format!("{:width$}", number, width = width)
+            };
+        }
+    }
+    value.to_string()
+}
+
+/// 设置页预览使用的示例数据
+pub fn sample_fields() -> BTreeMap<String, String> {
+    [
+        ("title", "示例视频"),
+        ("uploader", "某个频道"),
+        ("upload_date", "20240315"),
+        ("id", "dQw4w9WgXcQ"),
+        ("playlist", "示例播放列表"),
+        ("playlist_title", "示例播放列表"),
+        ("playlist_index", "7"),
+        ("resolution", "1920x1080"),
+        ("extractor", "youtube"),
+        ("ext", "mp4"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview(template: &str) -> String {
+        render(template, &sample_fields()).unwrap()
+    }
+
+    #[test]
+    fn renders_fields() {
+        assert_eq!(preview(DEFAULT_TEMPLATE), "示例视频.mp4");
+        assert_eq!(
+            preview("%(uploader)s/%(title)s [%(id)s].%(ext)s"),
+            "某个频道/示例视频 [dQw4w9WgXcQ].mp4"
+        );
+        assert_eq!(preview("%(duration)s.%(ext)s"), "NA.mp4");
+    }
+
+    #[test]
+    fn pads_numbers() {
+        assert_eq!(preview("%(playlist_index)03d"), "007");
+        assert_eq!(preview("%(playlist_index)3d"), "  7");
+        assert_eq!(preview("%(playlist_index)d"), "7");
+        assert_eq!(preview("%(title)05d"), "示例视频");
+    }
+
+    #[test]
+    fn falls_back_to_alternative_fields() {
+        assert_eq!(preview("%(playlist_title,playlist)s"), "示例播放列表");
+        assert_eq!(preview("%(album,playlist)s"), "示例播放列表");
+        assert_eq!(preview("%(album, title)s"), "示例视频");
+        assert_eq!(preview("%(album,artist)s"), "NA");
+        assert_eq!(preview("%(album,artist|未知)s"), "未知");
+    }
+
+    #[test]
+    fn replaces_and_defaults() {
+        assert_eq!(
+            preview("%(uploader&{} - |)s%(title)s"),
+            "某个频道 - 示例视频"
+        );
+        assert_eq!(preview("%(artist&{} - |)s%(title)s"), "示例视频");
+        assert_eq!(preview("%(artist|未知作者)s"), "未知作者");
+        assert_eq!(preview("%(artist|)s"), "");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(render("%(title", &sample_fields()).is_err());
+        assert!(render("%(title)", &sample_fields()).is_err());
+        assert!(render("%()s", &sample_fields()).is_err());
+
+        assert!(validate(DEFAULT_TEMPLATE).is_ok());
+        assert!(validate("%(uploader)s/%(title)s.%(ext)s").is_ok());
+        assert!(validate("").is_err());
+        assert!(validate("%(title)s").is_err());
+        assert!(validate("/abs/%(title)s.%(ext)s").is_err());
+        assert!(validate("C:/%(title)s.%(ext)s").is_err());
+        assert!(validate("../%(title)s.%(ext)s").is_err());
+    }
+}
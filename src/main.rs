@@ -1,6 +1,9 @@
 mod db;
+mod downloader;
 mod page;
+use db::Settings;
 use dioxus::prelude::*;
+use downloader::DownloadQueue;
 use page::Page;
 
 const MAIN_CSS: Asset = asset!("/assets/tailwind.css");
@@ -11,6 +14,16 @@ fn main() {
 
 #[component]
 fn App() -> Element {
+    // 下载队列挂在根组件上，切换页面不会丢失正在进行的任务
+    let queue = use_context_provider(|| {
+        let settings = Settings::load();
+        let queue = DownloadQueue::load(settings.max_concurrent_downloads);
+        queue.set_window(settings.download_window);
+        queue
+    });
+    // 订阅在后台定期检查，新视频直接加入队列
+    use_hook(|| downloader::subscriptions::spawn_sync(queue));
+
     rsx! {
         document::Stylesheet { href: MAIN_CSS }
         main {
@@ -1,7 +1,22 @@
+mod archive;
+mod formats;
+mod history;
 mod home;
+mod job;
+mod options;
+mod playlist;
+mod preview;
+mod proxy;
+mod ratelimit;
+mod rules;
 mod setting;
+mod subscriptions;
+mod subtitles;
+mod template;
 use dioxus::prelude::*;
 use home::Home;
+use std::thread;
+use tokio::sync::oneshot;
 use tokio::time::{sleep, Duration};
 
 use setting::Setting;
@@ -110,3 +125,19 @@ pub fn Page() -> Element {
         }
     }
 }
+
+/// 在后台线程执行阻塞操作（如运行 yt-dlp），不阻塞界面。
+/// 后台线程 panic 时返回错误，不影响界面
+pub async fn run_blocking<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    rx.await
+        .map_err(|_| "后台任务异常退出".to_string())
+        .and_then(|result| result)
+}
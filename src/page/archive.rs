@@ -0,0 +1,121 @@
+use crate::downloader::archive::{self, ArchiveEntry};
+use crate::downloader::DownloadQueue;
+use dioxus::prelude::*;
+
+/// 每次最多显示的存档条数
+const ARCHIVE_LIMIT: usize = 100;
+
+/// 下载存档：查看已记录的视频，删除后可重新下载
+#[component]
+pub fn ArchivePanel() -> Element {
+    let mut keyword = use_signal(String::new);
+    let mut entries = use_signal(archive::load);
+    let mut error = use_signal(|| None::<String>);
+    let mut confirm_clear = use_signal(|| false);
+    let queue = use_context::<DownloadQueue>();
+
+    let mut reload = move || entries.set(archive::load());
+
+    let keyword_text = keyword.read().trim().to_lowercase();
+    let matched: Vec<ArchiveEntry> = entries
+        .read()
+        .iter()
+        .rev()
+        .filter(|e| {
+            keyword_text.is_empty()
+                || e.id.to_lowercase().contains(&keyword_text)
+                || e.extractor.contains(&keyword_text)
+        })
+        .cloned()
+        .collect();
+    let total = entries.read().len();
+
+    rsx! {
+        div {
+            class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+            div {
+                class: "flex justify-between items-center mb-3 gap-3",
+                h2 {
+                    class: "text-lg font-semibold text-white",
+                    "下载存档"
+                }
+                input {
+                    r#type: "text",
+                    value: "{keyword}",
+                    placeholder: "搜索视频 ID 或站点...",
+                    class: "flex-1 min-w-0 bg-gray-700 text-white rounded-lg px-3 py-1 text-sm focus:outline-none focus:ring-2 focus:ring-blue-500",
+                    oninput: move |e| keyword.set(e.value()),
+                }
+                button {
+                    class: "text-sm text-gray-400 hover:text-white transition-colors",
+                    onclick: move |_| reload(),
+                    "刷新"
+                }
+                if *confirm_clear.read() {
+                    span { class: "text-sm text-red-400", "确定清空全部记录？" }
+                    button {
+                        class: "text-sm text-red-400 hover:text-red-300 transition-colors",
+                        onclick: {
+                            let queue = queue.clone();
+                            move |_| {
+                                error.set(queue.clear_archive().err());
+                                confirm_clear.set(false);
+                                reload();
+                            }
+                        },
+                        "确定"
+                    }
+                    button {
+                        class: "text-sm text-gray-400 hover:text-white transition-colors",
+                        onclick: move |_| confirm_clear.set(false),
+                        "取消"
+                    }
+                } else {
+                    button {
+                        class: "text-sm text-gray-400 hover:text-red-400 transition-colors",
+                        onclick: move |_| confirm_clear.set(true),
+                        "清空"
+                    }
+                }
+            }
+
+            if let Some(e) = error.read().as_ref() {
+                p { class: "mb-2 text-sm text-red-400", "{e}" }
+            }
+
+            div {
+                class: "max-h-[240px] overflow-y-auto space-y-1",
+                if matched.is_empty() {
+                    p { class: "text-sm text-gray-400", "没有记录" }
+                }
+                for entry in matched.iter().take(ARCHIVE_LIMIT).cloned() {
+                    div {
+                        key: "{entry.extractor}-{entry.id}",
+                        class: "flex items-center gap-3 text-sm bg-gray-700 rounded px-3 py-1",
+                        span {
+                            class: "px-2 rounded text-xs text-gray-300 bg-gray-600",
+                            "{entry.extractor}"
+                        }
+                        span { class: "flex-1 truncate text-gray-200 font-mono", "{entry.id}" }
+                        button {
+                            class: "text-gray-400 hover:text-red-400 transition-colors",
+                            onclick: {
+                                let queue = queue.clone();
+                                move |_| {
+                                    error.set(queue.remove_archive_entry(&entry).err());
+                                    reload();
+                                }
+                            },
+                            "删除"
+                        }
+                    }
+                }
+            }
+
+            p {
+                class: "mt-2 text-sm text-gray-400",
+                {format!("共 {} 条记录，已记录的视频再次下载时会被跳过，删除记录后可重新下载", total)}
+            }
+        }
+    }
+}
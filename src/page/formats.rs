@@ -0,0 +1,117 @@
+use crate::downloader::metadata::FormatInfo;
+use crate::downloader::progress::format_bytes;
+use dioxus::prelude::*;
+
+/// 视频与音频格式选择表格，`None` 表示交给 yt-dlp 自动选择
+#[component]
+pub fn FormatPicker(
+    video_formats: Vec<FormatInfo>,
+    audio_formats: Vec<FormatInfo>,
+    selected_video: Signal<Option<String>>,
+    selected_audio: Signal<Option<String>>,
+) -> Element {
+    rsx! {
+        div {
+            class: "space-y-4",
+
+            FormatTable {
+                title: "视频".to_string(),
+                formats: video_formats,
+                selected: selected_video,
+            }
+            FormatTable {
+                title: "音频".to_string(),
+                formats: audio_formats,
+                selected: selected_audio,
+            }
+        }
+    }
+}
+
+#[component]
+fn FormatTable(
+    title: String,
+    formats: Vec<FormatInfo>,
+    selected: Signal<Option<String>>,
+) -> Element {
+    let group = title.clone();
+
+    rsx! {
+        div {
+            h3 {
+                class: "text-sm font-semibold mb-2 text-gray-300",
+                "{title}"
+            }
+            div {
+                class: "max-h-[240px] overflow-y-auto rounded-lg border border-gray-600",
+                table {
+                    class: "w-full text-sm text-gray-300",
+                    thead {
+                        class: "bg-gray-700 text-gray-400 sticky top-0",
+                        tr {
+                            th { class: "px-2 py-1" }
+                            th { class: "px-2 py-1 text-left", "ID" }
+                            th { class: "px-2 py-1 text-left", "格式" }
+                            th { class: "px-2 py-1 text-left", "分辨率" }
+                            th { class: "px-2 py-1 text-left", "编码" }
+                            th { class: "px-2 py-1 text-right", "帧率" }
+                            th { class: "px-2 py-1 text-right", "码率" }
+                            th { class: "px-2 py-1 text-right", "大小" }
+                        }
+                    }
+                    tbody {
+                        tr {
+                            class: "border-t border-gray-700 hover:bg-gray-700 cursor-pointer",
+                            onclick: move |_| selected.set(None),
+                            td {
+                                class: "px-2 py-1",
+                                input {
+                                    r#type: "radio",
+                                    name: "{group}",
+                                    checked: selected.read().is_none(),
+                                }
+                            }
+                            td { class: "px-2 py-1", colspan: "7", "自动选择" }
+                        }
+                        for format in formats {
+                            {
+                                let id = format.format_id.clone();
+                                let codec = [format.vcodec.clone(), format.acodec.clone()]
+                                    .into_iter()
+                                    .flatten()
+                                    .filter(|c| c != "none")
+                                    .collect::<Vec<_>>()
+                                    .join(" + ");
+                                rsx! {
+                                    tr {
+                                        key: "{id}",
+                                        class: "border-t border-gray-700 hover:bg-gray-700 cursor-pointer",
+                                        onclick: {
+                                            let id = id.clone();
+                                            move |_| selected.set(Some(id.clone()))
+                                        },
+                                        td {
+                                            class: "px-2 py-1",
+                                            input {
+                                                r#type: "radio",
+                                                name: "{group}",
+                                                checked: selected.read().as_deref() == Some(id.as_str()),
+                                            }
+                                        }
+                                        td { class: "px-2 py-1", "{id}" }
+                                        td { class: "px-2 py-1", {format.ext.clone().unwrap_or_default()} }
+                                        td { class: "px-2 py-1", {format.resolution_label()} }
+                                        td { class: "px-2 py-1 truncate max-w-[160px]", title: "{codec}", "{codec}" }
+                                        td { class: "px-2 py-1 text-right", {format.fps.map(|f| format!("{:.0}", f)).unwrap_or_default()} }
+                                        td { class: "px-2 py-1 text-right", {format.tbr.map(|t| format!("{:.0}k", t)).unwrap_or_default()} }
+                                        td { class: "px-2 py-1 text-right", {format.size().map(format_bytes).unwrap_or_default()} }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
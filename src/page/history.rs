@@ -0,0 +1,140 @@
+use crate::db::{self, HistoryDb, HistoryEntry};
+use crate::downloader::progress::format_bytes;
+use chrono::{Local, TimeZone};
+use dioxus::prelude::*;
+
+/// 每次最多显示的历史条数
+const HISTORY_LIMIT: usize = 50;
+
+fn load_history(keyword: &str) -> Result<Vec<HistoryEntry>, String> {
+    let history = HistoryDb::open().map_err(|e| format!("打开历史数据库失败: {}", e))?;
+    let entries = if keyword.trim().is_empty() {
+        history.recent(HISTORY_LIMIT)
+    } else {
+        history.search(keyword.trim(), HISTORY_LIMIT)
+    };
+    entries.map_err(|e| format!("读取历史记录失败: {}", e))
+}
+
+pub fn format_time(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn status_label(status: &str) -> &'static str {
+    match status {
+        db::STATUS_RUNNING => "下载中",
+        db::STATUS_PAUSED => "已暂停",
+        db::STATUS_COMPLETED => "已完成",
+        db::STATUS_FAILED => "失败",
+        db::STATUS_CANCELLED => "已取消",
+        db::STATUS_SKIPPED => "已跳过",
+        _ => "未知",
+    }
+}
+
+/// 下载历史列表
+#[component]
+pub fn HistoryPanel() -> Element {
+    let mut keyword = use_signal(String::new);
+    let mut entries = use_signal(|| load_history(""));
+
+    let mut reload = move || entries.set(load_history(&keyword.read()));
+
+    rsx! {
+        div {
+            class: "bg-gray-800 rounded-lg p-6 shadow-lg hover:shadow-xl transition-shadow duration-200",
+
+            div {
+                class: "flex justify-between items-center mb-4 gap-4",
+                h2 {
+                    class: "text-xl font-semibold bg-gradient-to-r from-blue-500 to-purple-500 bg-clip-text text-transparent",
+                    "下载历史"
+                }
+                input {
+                    r#type: "text",
+                    value: "{keyword}",
+                    placeholder: "搜索标题、链接或站点...",
+                    class: "flex-1 bg-gray-700 text-white rounded-lg px-3 py-1 text-sm focus:outline-none focus:ring-2 focus:ring-blue-500",
+                    oninput: move |e| {
+                        keyword.set(e.value());
+                        reload();
+                    }
+                }
+                button {
+                    class: "text-sm text-gray-400 hover:text-white transition-colors duration-200",
+                    onclick: move |_| reload(),
+                    "刷新"
+                }
+                button {
+                    class: "text-sm text-gray-400 hover:text-red-400 transition-colors duration-200",
+                    onclick: move |_| {
+                        if let Ok(history) = HistoryDb::open() {
+                            let _ = history.clear();
+                        }
+                        reload();
+                    },
+                    "清空"
+                }
+            }
+
+            match &*entries.read() {
+                Err(e) => rsx! {
+                    p { class: "text-red-400", "{e}" }
+                },
+                Ok(list) if list.is_empty() => rsx! {
+                    p { class: "text-gray-400", "暂无下载记录" }
+                },
+                Ok(list) => rsx! {
+                    ul {
+                        class: "space-y-2 max-h-[400px] overflow-y-auto",
+                        for entry in list.iter().cloned() {
+                            li {
+                                key: "{entry.id}",
+                                class: "bg-gray-700 rounded-lg px-4 py-2 flex items-center gap-3",
+
+                                div {
+                                    class: "flex-1 min-w-0",
+                                    p {
+                                        class: "truncate text-gray-200",
+                                        title: "{entry.url}",
+                                        {entry.title.clone().unwrap_or(entry.url.clone())}
+                                    }
+                                    p {
+                                        class: "text-xs text-gray-400 truncate",
+                                        {
+                                            [
+                                                Some(format_time(entry.started_at)),
+                                                entry.site.clone(),
+                                                entry.size.map(|s| format_bytes(s as f64)),
+                                                Some(status_label(&entry.status).to_string()),
+                                                entry.error.clone(),
+                                            ]
+                                            .into_iter()
+                                            .flatten()
+                                            .collect::<Vec<_>>()
+                                            .join(" · ")
+                                        }
+                                    }
+                                }
+                                button {
+                                    class: "text-sm text-gray-400 hover:text-red-400 transition-colors duration-200",
+                                    onclick: move |_| {
+                                        if let Ok(history) = HistoryDb::open() {
+                                            let _ = history.delete(entry.id);
+                                        }
+                                        reload();
+                                    },
+                                    "删除"
+                                }
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
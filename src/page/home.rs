@@ -1,22 +1,58 @@
 // use crate::db::SettingsDatabase;
 use crate::db::Settings;
+use crate::downloader::metadata::{self, VideoInfo};
+use crate::downloader::playlist::format_ranges;
+use crate::downloader::template;
+use crate::downloader::{DownloadQueue, DownloadRequest};
+use crate::page::formats::FormatPicker;
+use crate::page::history::HistoryPanel;
+use crate::page::job::JobCard;
+use crate::page::options::JobOptions;
+use crate::page::playlist::PlaylistPicker;
+use crate::page::preview::PreviewCard;
+use crate::page::run_blocking;
+use crate::page::subtitles::SubtitlePicker;
 use dioxus::prelude::*;
 use regex::Regex;
-use std::env::temp_dir;
-use std::{
-    io::{BufRead, BufReader},
-    process::{Command, Stdio},
-    sync::mpsc,
-    thread,
-};
-use tokio;
+use std::collections::BTreeSet;
 use tokio::time::{self, Duration};
 #[component]
 pub fn Home() -> Element {
     let mut video_text = use_signal(|| String::new());
-    let mut is_downloading = use_signal(|| false);
-    let mut download_status = use_signal(|| String::new());
     let settings = use_signal(Settings::load);
+    let queue = use_context::<DownloadQueue>();
+    let mut jobs = use_signal(|| queue.jobs());
+    let mut restored = use_signal(|| queue.restored());
+    // 解析出的视频信息与所选格式，链接变化时清空
+    let mut video_info = use_signal(|| None::<VideoInfo>);
+    let mut info_error = use_signal(|| None::<String>);
+    let mut fetching = use_signal(|| false);
+    let mut selected_video = use_signal(|| None::<String>);
+    let mut selected_audio = use_signal(|| None::<String>);
+    // 播放列表中选中的条目序号
+    let mut selected_entries = use_signal(BTreeSet::<usize>::new);
+    // 本次下载的选项，初始值来自设置，提交后恢复
+    let mut job_options = use_signal(|| DownloadRequest::from_settings("", &settings.read()));
+
+    // 定期从队列拉取最新状态
+    use_future({
+        let queue = queue.clone();
+        move || {
+            let queue = queue.clone();
+            async move {
+                let mut seen = queue.version();
+                loop {
+                    time::sleep(Duration::from_millis(300)).await;
+                    let version = queue.version();
+                    if version != seen {
+                        seen = version;
+                        jobs.set(queue.jobs());
+                        restored.set(queue.restored());
+                    }
+                }
+            }
+        }
+    });
 
     let instructions = vec![
         Instruction::new(
@@ -35,177 +71,124 @@ pub fn Home() -> Element {
         Instruction::new(5, "首次下载记得设置下载路径".into()),
     ];
 
-    let mut download_video = move || {
-        is_downloading.set(true);
-        download_status.set(String::from("开始下载..."));
-        let url = video_text.read().to_string();
-        if url.is_empty() {
-            is_downloading.set(false);
-            download_status.set(String::from("请输入有效的视频链接"));
-            ()
-        }
-
-        // 1. 创建临时文件路径
-        let temp_path = temp_dir().join("yt-dlp-temp");
-        let ffmpeg_path = temp_dir().join("ffmpeg-temp");
-        #[cfg(not(windows))]
-        {
-            // 2. 写入 yt-dlp 二进制
-            let yt_dlp_exe = include_bytes!("./bin/yt-dlp");
-            std::fs::write(&temp_path, yt_dlp_exe)
-                .map_err(|e| format!("写入临时文件失败: {}", e))
-                .unwrap();
-
-            let ffmpeg_exe = include_bytes!("./bin/ffmpeg");
-            std::fs::write(&ffmpeg_path, ffmpeg_exe)
-                .map_err(|e| format!("写入临时文件失败: {}", e))
-                .unwrap();
-        }
-
-        #[cfg(windows)]
-        {
-            yt_dlp_bytes = include_bytes!("./bin/yt-dlp.exe");
-            ffmpeg_bytes = include_bytes!("./bin/ffmpeg.exe");
-            yt_dlp_name = "yt-dlp-temp.exe";
-            ffmpeg_name = "ffmpeg-temp.exe";
-        }
-
-        // 3. 在 Unix 系统上设置可执行权限
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o755))
-                .map_err(|e| format!("设置可执行权限失败: {}", e))
-                .unwrap();
-
-            std::fs::set_permissions(&ffmpeg_path, std::fs::Permissions::from_mode(0o755))
-                .map_err(|e| format!("设置可执行权限失败: {}", e))
-                .unwrap();
-        }
-
-        let (tx, rs) = mpsc::channel::<String>();
-        let dp = settings.read().download_path.clone();
-        let output_template = format!("{}/%(title)s.%(ext)s", dp);
-        let is_down_list = settings.read().down_video_list.clone();
-        let qua = settings.read().quality.clone();
-
-        thread::spawn({
-            move || {
-                let mut cmd = Command::new(&temp_path); // 先创建基础命令
-
-                // 根据URL类型添加不同参数
-                if url.contains("youtube.com") || url.contains("youtu.be") {
-                    // YouTube 专用下载逻辑
-                    cmd.arg(&url)
-                        .arg("--newline")
-                        .arg("--no-check-certificate")
-                        .arg("--ffmpeg-location")
-                        .arg(&ffmpeg_path)
-                        .arg("--retries")
-                        .arg("10")
-                        .arg("-o")
-                        .arg(&output_template)
-                        .args(if is_down_list.to_string() == "no" {
-                            vec!["--no-playlist"]
-                        } else {
-                            vec![]
-                        });
-                } else {
-                    // 其他网站下载逻辑
-                    cmd.arg(&url)
-                        .arg("--newline")
-                        .arg("--no-check-certificate")
-                        .arg("--ffmpeg-location")
-                        .arg(&ffmpeg_path)
-                        .args(match qua.as_str() {
-                            "best" => vec![
-                                "-f",
-                                "bestvideo[ext=mp4]+bestaudio[ext=m4a]/best[ext=mp4]/best",
-                                "--merge-output-format",
-                                "mp4",
-                            ],
-                            _ => vec!["-f", "best[ext=mp4]"],
-                        })
-                        .arg("-o")
-                        .arg(&output_template)
-                        .args(if is_down_list.to_string() == "no" {
-                            vec!["--no-playlist"]
-                        } else {
-                            vec![]
-                        });
-                }
+    let mut reset_info = move || {
+        video_info.set(None);
+        info_error.set(None);
+        selected_video.set(None);
+        selected_audio.set(None);
+        selected_entries.set(BTreeSet::new());
+    };
 
-                let mut child = cmd
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .spawn()
-                    .expect("无法启动进程");
-
-                // 获取输出流
-                let stdout = child.stdout.take().unwrap();
-                let stderr = child.stderr.take().unwrap();
-
-                // 创建读取线程
-                let tx_stdout = tx.clone();
-                let stdout_thread = thread::spawn(move || {
-                    let reader = BufReader::new(stdout);
-                    for line in reader.lines() {
-                        tx_stdout.send(line.unwrap_or_default()).unwrap();
+    let mut download_video = {
+        let queue = queue.clone();
+        move || {
+            let url = video_text.read().trim().to_string();
+            if url.is_empty() || job_options.read().validate().is_err() {
+                return;
+            }
+            let mut request = job_options.read().clone().with_url(url, &settings.read());
+            // 没有解析出视频信息时无法确认是播放列表，不创建播放列表文件夹；
+            // 单个视频总是重新下载，不受下载存档影响
+            let is_playlist = video_info.read().as_ref().is_some_and(|i| i.is_playlist());
+            request.playlist_folder &= is_playlist;
+            request.use_archive &= is_playlist;
+            if let Some(info) = video_info.read().as_ref() {
+                // 只下载选中的播放列表条目，全选时不需要 --playlist-items
+                let videos = info.video_entries();
+                if info.is_playlist() && !videos.is_empty() {
+                    let selected = selected_entries.read();
+                    if selected.is_empty() {
+                        return;
                     }
-                });
-
-                let tx_stderr = tx.clone();
-                let stderr_thread = thread::spawn(move || {
-                    let reader = BufReader::new(stderr);
-                    for line in reader.lines() {
-                        tx_stderr.send(line.unwrap_or_default()).unwrap();
+                    request.download_playlist = true;
+                    if selected.len() < videos.len() {
+                        if info.is_nested() {
+                            request.playlist_ids = selected
+                                .iter()
+                                .filter_map(|&i| videos.get(i - 1).and_then(|v| v.id.clone()))
+                                .collect();
+                            if request.playlist_ids.is_empty() {
+                                return;
+                            }
+                        } else {
+                            request.playlist_items = format_ranges(&selected);
+                        }
                     }
-                });
-
-                let status = child.wait().unwrap();
-
-                // 加入线程
-                stdout_thread.join().unwrap();
-                stderr_thread.join().unwrap();
-
-                if status.success() {
-                    let _ = std::fs::remove_file(&temp_path);
-                    let _ = std::fs::remove_file(&ffmpeg_path);
-                    tx.send("下载完成".to_string()).unwrap();
-                } else {
-                    tx.send(format!("线程退出状态：{}", status)).unwrap();
                 }
+                let find = |id: &Option<String>| {
+                    id.as_ref()
+                        .and_then(|id| info.formats.iter().find(|f| &f.format_id == id))
+                };
+                request.format = metadata::format_selector(
+                    find(&selected_video.read()),
+                    find(&selected_audio.read()),
+                );
             }
-        });
+            queue.enqueue(request);
+            jobs.set(queue.jobs());
+            job_options.set(DownloadRequest::from_settings("", &settings.read()));
+            reset_info();
+            fetching.set(false);
+            video_text.set(String::new());
+        }
+    };
 
+    // 后台解析链接，结果返回时若链接已变化则丢弃
+    let mut resolve_url = move || {
+        let url = video_text.read().trim().to_string();
+        if url.is_empty() {
+            return;
+        }
+        let request = job_options
+            .read()
+            .clone()
+            .with_url(url.clone(), &settings.read());
+        reset_info();
+        fetching.set(true);
         spawn(async move {
-            while let Ok(line) = rs.recv() {
-                download_status.with_mut(|t| {
-                    t.push_str(&line);
-                    t.push('\n');
-                });
-                let _ = document::eval(
-                    r#"
-                                const container = document.getElementById("output-container");
-                                if (container) {
-                                    container.scrollTop = container.scrollHeight+10;
-                                }
-                                "#,
-                );
-
-                time::sleep(Duration::from_millis(500)).await;
+            let result = run_blocking(move || metadata::fetch_info(&request)).await;
+            if video_text.read().trim() != url {
+                return;
+            }
+            match result {
+                Ok(info) => {
+                    selected_entries.set((1..=info.video_entries().len()).collect());
+                    video_info.set(Some(info));
+                }
+                Err(e) => info_error.set(Some(e)),
             }
+            fetching.set(false);
         });
+    };
 
-        is_downloading.set(false);
+    let clear_finished = {
+        let queue = queue.clone();
+        move |_| {
+            queue.clear_finished();
+            jobs.set(queue.jobs());
+        }
     };
 
-    // end
+    let restore_actions = {
+        let queue = queue.clone();
+        move |action: RestoreAction| {
+            match action {
+                RestoreAction::Resume => queue.resume_restored(),
+                RestoreAction::Discard => {
+                    queue.discard_restored(settings.read().clean_partial_on_cancel == "yes")
+                }
+                RestoreAction::Dismiss => queue.dismiss_restored(),
+            }
+            jobs.set(queue.jobs());
+            restored.set(queue.restored());
+        }
+    };
 
     let mut handle_clear = move || {
         // 实现清除输入的逻辑
+        reset_info();
+        fetching.set(false);
         video_text.set(String::new());
-        download_status.set(String::new());
     };
 
     let extract_url = move |text: &str| -> Option<String> {
@@ -247,64 +230,45 @@ pub fn Home() -> Element {
                         class: "flex-1 bg-gray-700 text-white rounded-lg px-4 py-2 focus:outline-none focus:ring-2 focus:ring-blue-500 transition-all duration-200",
                         oninput: move |e| {
                                        if let Some(url) = extract_url(&e.value()) {
+                                           let changed = *video_text.read() != url;
                                            video_text.set(url); // 仅设置解析后的 URL
+                                           if changed {
+                                               resolve_url();
+                                           }
                                        }
                                    }
                     }
 
                     button {
-                        disabled: "{is_downloading}",
-                        onclick: move |_| download_video()
-                        ,
+                        disabled: video_text.read().is_empty() || job_options.read().validate().is_err(),
+                        onclick: move |_| download_video(),
                         class: "bg-blue-600 hover:bg-blue-700 text-white px-6 py-2 rounded-lg transition-all duration-200 flex items-center gap-2 hover:scale-105 active:scale-95 disabled:opacity-50 disabled:cursor-not-allowed",
 
-                        if *is_downloading.read() {
-
-                                svg {
-                                    class: "h-5 w-5 mr-3 -ml-1 size-5 animate-spin text-white",
-                                    xmlns: "http://www.w3.org/2000/svg",
-                                    fill: "none",
-                                    view_box: "0 0 24 24",
-
-                                    circle {
-                                        class: "opacity-25",
-                                        cx: "12",
-                                        cy: "12",
-                                        r: "10",
-                                        stroke: "currentColor",
-                                        stroke_width: "4"
-                                    }
-                                    path {
-                                        class: "opacity-75",
-                                        fill: "currentColor",
-                                        d: "M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4zm2 5.291A7.962 7.962 0 014 12H0c0 3.042 1.135 5.824 3 7.938l3-2.647z"
-                                    }
-                                }
-                                "下载中..."
-
-                        } else {
-
-                                svg {
-                                    xmlns: "http://www.w3.org/2000/svg",
-                                    class: "h-5 w-5",
-                                    fill: "none",
-                                    view_box: "0 0 24 24",
-                                    stroke: "currentColor",
-
-                                    path {
-                                        stroke_linecap: "round",
-                                        stroke_linejoin: "round",
-                                        stroke_width: "2",
-                                        d: "M4 16v1a3 3 0 003 3h10a3 3 0 003-3v-1m-4-4l-4 4m0 0l-4-4m4 4V4"
-                                    }
-                                }
-                                "下载"
+                        svg {
+                            xmlns: "http://www.w3.org/2000/svg",
+                            class: "h-5 w-5",
+                            fill: "none",
+                            view_box: "0 0 24 24",
+                            stroke: "currentColor",
 
+                            path {
+                                stroke_linecap: "round",
+                                stroke_linejoin: "round",
+                                stroke_width: "2",
+                                d: "M4 16v1a3 3 0 003 3h10a3 3 0 003-3v-1m-4-4l-4 4m0 0l-4-4m4 4V4"
+                            }
                         }
+                        "下载"
+                    }
+
+                    button {
+                        disabled: video_text.read().is_empty() || fetching(),
+                        onclick: move |_| resolve_url(),
+                        class: "bg-gray-700 hover:bg-gray-600 text-white px-4 py-2 rounded-lg transition-all duration-200 hover:scale-105 active:scale-95 disabled:opacity-50 disabled:cursor-not-allowed",
+                        if fetching() { "解析中..." } else { "重新解析" }
                     }
 
                     button {
-                        disabled: "{is_downloading}",
                         onclick: move |_| handle_clear(),
                         class: "bg-gray-700 hover:bg-gray-600 text-white px-4 py-2 rounded-lg transition-all duration-200 hover:scale-105 active:scale-95 disabled:opacity-50 disabled:cursor-not-allowed",
 
@@ -324,30 +288,164 @@ pub fn Home() -> Element {
                         }
                     }
                 }
+
+                JobOptions { request: job_options }
+            }
+
+            // 视频信息预览与格式选择
+            if fetching() {
+                div {
+                    class: "bg-gray-800 rounded-lg p-4 shadow-lg text-sm text-gray-400",
+                    "正在解析视频信息..."
+                }
+            }
+            if let Some(e) = info_error.read().as_ref() {
+                div {
+                    class: "bg-gray-800 rounded-lg p-4 shadow-lg text-sm text-red-400",
+                    "{e}"
+                }
+            }
+            if let Some(info) = video_info.read().as_ref() {
+                div {
+                    class: "bg-gray-800 rounded-lg p-6 shadow-lg",
+
+                    PreviewCard { info: info.clone() }
+
+                    if let Ok(filename) = template::render(&job_options.read().output_template(), &info.template_fields()) {
+                        p {
+                            class: "mt-3 text-sm text-gray-400 break-all",
+                            "保存为: "
+                            span { class: "text-gray-200", "{filename}" }
+                        }
+                    }
+
+                    if info.is_playlist() && !info.video_entries().is_empty() {
+                        PlaylistPicker {
+                            entries: info.video_entries().into_iter().cloned().collect::<Vec<_>>(),
+                            selected: selected_entries,
+                            request: job_options,
+                        }
+                    }
+
+                    if !info.subtitles.is_empty() || !info.automatic_captions.is_empty() {
+                        SubtitlePicker { info: info.clone(), request: job_options }
+                    }
+
+                    if !info.formats.is_empty() {
+                        details {
+                            class: "mt-4",
+                            summary {
+                                class: "text-sm text-gray-400 cursor-pointer hover:text-white",
+                                {
+                                    match metadata::format_selector(
+                                        info.formats.iter().find(|f| Some(&f.format_id) == selected_video.read().as_ref()),
+                                        info.formats.iter().find(|f| Some(&f.format_id) == selected_audio.read().as_ref()),
+                                    ) {
+                                        Some(selector) => format!("选择格式（已选择: {}）", selector),
+                                        None => "选择格式（按设置中的画质自动选择）".to_string(),
+                                    }
+                                }
+                            }
+                            div {
+                                class: "mt-3",
+                                FormatPicker {
+                                    video_formats: info.video_formats(),
+                                    audio_formats: info.audio_formats(),
+                                    selected_video,
+                                    selected_audio,
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // 上次未完成的任务
+            if !restored.read().is_empty() {
+                div {
+                    class: "bg-gray-800 rounded-lg p-6 shadow-lg border border-blue-600",
+
+                    p {
+                        class: "text-gray-200",
+                        {format!("上次有 {} 个任务未完成", restored.read().len())}
+                    }
+                    {
+                        let partial_count: usize = restored.read().iter().map(|r| r.partial_files.len()).sum();
+                        rsx! {
+                            if partial_count > 0 {
+                                p {
+                                    class: "mt-1 text-sm text-gray-400",
+                                    "下载目录中检测到 {partial_count} 个部分下载文件，继续下载将断点续传"
+                                }
+                            }
+                        }
+                    }
+                    div {
+                        class: "mt-4 flex gap-3",
+                        button {
+                            class: "bg-blue-600 hover:bg-blue-700 text-white px-4 py-2 rounded-lg transition-colors",
+                            onclick: {
+                                let mut restore_actions = restore_actions.clone();
+                                move |_| restore_actions(RestoreAction::Resume)
+                            },
+                            "全部继续"
+                        }
+                        button {
+                            class: "bg-gray-700 hover:bg-gray-600 text-white px-4 py-2 rounded-lg transition-colors",
+                            onclick: {
+                                let mut restore_actions = restore_actions.clone();
+                                move |_| restore_actions(RestoreAction::Dismiss)
+                            },
+                            "稍后处理"
+                        }
+                        button {
+                            class: "bg-gray-700 hover:bg-red-600 text-white px-4 py-2 rounded-lg transition-colors",
+                            onclick: {
+                                let mut restore_actions = restore_actions.clone();
+                                move |_| restore_actions(RestoreAction::Discard)
+                            },
+                            "放弃"
+                        }
+                    }
+                }
             }
 
-            // 输出区域
+            // 下载队列
             div {
                 class: "bg-gray-800 rounded-lg p-6 shadow-lg hover:shadow-xl transition-shadow duration-200",
 
-                h2 {
-                    class: "text-xl font-semibold mb-4 bg-gradient-to-r from-blue-500 to-purple-500 bg-clip-text text-transparent",
-                    "下载状态"
+                div {
+                    class: "flex justify-between items-center mb-4",
+                    h2 {
+                        class: "text-xl font-semibold bg-gradient-to-r from-blue-500 to-purple-500 bg-clip-text text-transparent",
+                        "下载状态"
+                    }
+                    button {
+                        class: "text-sm text-gray-400 hover:text-white transition-colors duration-200",
+                        onclick: clear_finished,
+                        "清除已完成"
+                    }
                 }
 
                 div {
-                    id:"output-container",
+                    class: "space-y-4",
 
-                    class: "bg-gray-700 rounded-lg p-4 min-h-[200px] whitespace-pre-line overflow-y-auto max-h-[400px]",
-
-                    p {
+                    if jobs.read().is_empty() {
+                        p {
+                            class: "text-gray-400",
+                            "暂无下载任务"
+                        }
+                    }
 
-                        class: "text-gray-300",
-                        "{download_status}"
+                    for job in jobs.read().iter().cloned() {
+                        JobCard { key: "{job.id}", job }
                     }
                 }
             }
 
+            // 下载历史
+            HistoryPanel {}
+
             // 使用说明
             div {
                 class: "mt-8 bg-gray-800 rounded-lg p-6 shadow-lg hover:shadow-xl transition-shadow duration-200",
@@ -413,3 +511,10 @@ impl Instruction {
         Self { id, text }
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+enum RestoreAction {
+    Resume,
+    Discard,
+    Dismiss,
+}
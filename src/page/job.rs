@@ -0,0 +1,181 @@
+use crate::db::Settings;
+use crate::downloader::progress::{format_bytes, format_duration};
+use crate::downloader::{DownloadQueue, JobStatus, QueuedJob};
+use chrono::{Local, TimeZone};
+use dioxus::prelude::*;
+
+/// 今天只显示时间，否则带上日期
+fn format_start(timestamp: i64) -> String {
+    let Some(at) = Local.timestamp_opt(timestamp, 0).single() else {
+        return String::new();
+    };
+    if at.date_naive() == Local::now().date_naive() {
+        at.format("%H:%M:%S").to_string()
+    } else {
+        at.format("%m-%d %H:%M").to_string()
+    }
+}
+
+/// 队列中单个任务的状态卡片
+#[component]
+pub fn JobCard(job: QueuedJob) -> Element {
+    let queue = use_context::<DownloadQueue>();
+    let mut action_error = use_signal(|| None::<String>);
+    let badge_class = match job.status {
+        JobStatus::Queued => "bg-gray-600",
+        JobStatus::Running => "bg-blue-600",
+        JobStatus::Paused => "bg-purple-600",
+        JobStatus::WindowPaused => "bg-indigo-600",
+        JobStatus::Completed => "bg-green-600",
+        JobStatus::Skipped => "bg-teal-600",
+        JobStatus::Failed(_) => "bg-red-600",
+        JobStatus::Cancelled => "bg-yellow-600",
+    };
+
+    let cancel = {
+        let queue = queue.clone();
+        let id = job.id;
+        move |_| {
+            let remove_partials = Settings::load().clean_partial_on_cancel == "yes";
+            action_error.set(queue.cancel(id, remove_partials).err());
+        }
+    };
+
+    let pause = {
+        let queue = queue.clone();
+        let id = job.id;
+        move |_| action_error.set(queue.pause(id).err())
+    };
+
+    let resume = {
+        let queue = queue.clone();
+        let id = job.id;
+        move |_| queue.resume(id)
+    };
+
+    let next_start = queue.next_start(&job);
+
+    let progress = &job.progress;
+    let percent = match job.status {
+        JobStatus::Completed => 100.0,
+        _ => progress.percent.unwrap_or(0.0),
+    };
+    let details = [
+        progress.total_bytes.map(|b| format_bytes(b as f64)),
+        progress.speed.map(|s| format!("{}/s", format_bytes(s))),
+        progress.eta.map(|e| format!("剩余 {}", format_duration(e))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ");
+
+    rsx! {
+        div {
+            class: "bg-gray-700 rounded-lg p-4",
+
+            div {
+                class: "flex items-center gap-3",
+                span {
+                    class: "px-2 py-1 rounded text-xs text-white {badge_class}",
+                    {job.status.label()}
+                }
+                span {
+                    class: "flex-1 truncate text-gray-200",
+                    title: "{job.request.url}",
+                    "{job.request.url}"
+                }
+                if matches!(job.status, JobStatus::Queued | JobStatus::Running | JobStatus::WindowPaused) {
+                    button {
+                        class: "text-sm text-gray-400 hover:text-white transition-colors duration-200",
+                        onclick: pause,
+                        "暂停"
+                    }
+                }
+                if job.status == JobStatus::Paused {
+                    button {
+                        class: "text-sm text-gray-400 hover:text-white transition-colors duration-200",
+                        onclick: resume,
+                        "继续"
+                    }
+                }
+                if !job.status.is_finished() {
+                    button {
+                        class: "text-sm text-gray-400 hover:text-red-400 transition-colors duration-200",
+                        onclick: cancel,
+                        "取消"
+                    }
+                }
+            }
+
+            if let Some(next_start) = next_start {
+                p {
+                    class: "mt-2 text-sm text-gray-400",
+                    {
+                        let at = format_start(next_start);
+                        if job.status == JobStatus::WindowPaused {
+                            format!("将于 {} 继续下载", at)
+                        } else if job.attempts > 0 {
+                            format!("将于 {} 进行第 {} 次重试", at, job.attempts)
+                        } else {
+                            format!("将于 {} 开始", at)
+                        }
+                    }
+                }
+            }
+
+            if let Some(e) = action_error.read().as_ref() {
+                p {
+                    class: "mt-2 text-sm text-red-400",
+                    "{e}"
+                }
+            }
+
+            if matches!(job.status, JobStatus::Running | JobStatus::Paused | JobStatus::WindowPaused) {
+                div {
+                    class: "mt-3",
+                    div {
+                        class: "w-full bg-gray-800 rounded-full h-2 overflow-hidden",
+                        div {
+                            class: "bg-gradient-to-r from-blue-500 to-purple-500 h-2 transition-all duration-300",
+                            style: "width: {percent:.1}%",
+                        }
+                    }
+                    div {
+                        class: "mt-1 flex justify-between text-xs text-gray-400",
+                        span { "{progress.stage.label()} {percent:.1}%" }
+                        span { "{details}" }
+                    }
+                }
+            }
+
+            if job.status == JobStatus::Skipped {
+                p {
+                    class: "mt-2 text-sm text-gray-400",
+                    "已在下载存档中，没有重新下载；在设置中删除存档记录后可以重新下载"
+                }
+            }
+
+            if let JobStatus::Failed(e) = &job.status {
+                p {
+                    class: "mt-2 text-sm text-red-400",
+                    "{e}"
+                }
+            }
+
+            if !job.log.is_empty() {
+                details {
+                    class: "mt-2",
+                    summary {
+                        class: "text-sm text-gray-400 cursor-pointer hover:text-white",
+                        "日志"
+                    }
+                    div {
+                        class: "mt-2 bg-gray-800 rounded p-2 text-xs text-gray-300 whitespace-pre-line overflow-y-auto max-h-[200px]",
+                        {job.log.join("\n")}
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::downloader::{parse_rate, DownloadRequest};
+use crate::page::setting::CheckboxInput;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use dioxus::prelude::*;
+
+/// 可提取的音频格式
+pub const AUDIO_FORMATS: [(&str, &str); 4] = [
+    ("mp3", "MP3"),
+    ("m4a", "M4A (AAC)"),
+    ("opus", "Opus"),
+    ("flac", "FLAC（无损）"),
+];
+
+/// `--audio-quality` 可选值，无损格式会忽略码率
+pub const AUDIO_QUALITIES: [(&str, &str); 4] = [
+    ("0", "最佳"),
+    ("320K", "320 kbps"),
+    ("192K", "192 kbps"),
+    ("128K", "128 kbps"),
+];
+
+/// `--convert-subs` 可选值，空字符串表示保持原格式
+pub const SUBTITLE_FORMATS: [(&str, &str); 4] = [
+    ("", "保持原格式"),
+    ("srt", "SRT"),
+    ("ass", "ASS"),
+    ("vtt", "VTT"),
+];
+
+/// `datetime-local` 输入框使用的格式
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+fn format_start_at(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|t| Local.timestamp_opt(t, 0).single())
+        .map(|t| t.format(DATETIME_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+fn parse_start_at(text: &str) -> Option<i64> {
+    let time = NaiveDateTime::parse_from_str(text, DATETIME_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|t| t.timestamp())
+}
+
+/// 只对本次下载生效的选项，默认值来自设置
+#[component]
+pub fn JobOptions(request: Signal<DownloadRequest>) -> Element {
+    let audio_only = request.read().audio_only;
+    let mode = if audio_only { "audio" } else { "video" };
+    let clip_error = request.read().clip.parse().err();
+    let rate_error = request
+        .read()
+        .rate_limit_override
+        .as_deref()
+        .and_then(|limit| parse_rate(limit).err());
+
+    rsx! {
+        details {
+            class: "mt-4",
+            summary {
+                class: "text-sm text-gray-400 cursor-pointer hover:text-white",
+                "本次下载选项"
+            }
+            div {
+                class: "mt-3 space-y-2 text-sm",
+
+                OptionRow {
+                    label: "下载内容".to_string(),
+                    options: vec![("video", "视频"), ("audio", "仅音频")],
+                    value: mode.to_string(),
+                    on_change: move |value: String| request.write().audio_only = value == "audio",
+                }
+                if audio_only {
+                    OptionRow {
+                        label: "音频格式".to_string(),
+                        options: AUDIO_FORMATS.to_vec(),
+                        value: request.read().audio_format.clone(),
+                        on_change: move |value: String| request.write().audio_format = value,
+                    }
+                    OptionRow {
+                        label: "音频质量".to_string(),
+                        options: AUDIO_QUALITIES.to_vec(),
+                        value: request.read().audio_quality.clone(),
+                        on_change: move |value: String| request.write().audio_quality = value,
+                    }
+                }
+                div {
+                    class: "flex items-center justify-between gap-4",
+                    span { class: "text-gray-300", "截取片段" }
+                    div {
+                        class: "flex items-center gap-2",
+                        input {
+                            class: "w-24 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                            placeholder: "开始 0:00",
+                            value: "{request.read().clip.start}",
+                            oninput: move |e| request.write().clip.start = e.value(),
+                        }
+                        span { class: "text-gray-400", "-" }
+                        input {
+                            class: "w-24 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                            placeholder: "结束",
+                            value: "{request.read().clip.end}",
+                            oninput: move |e| request.write().clip.end = e.value(),
+                        }
+                    }
+                }
+                if let Some(e) = clip_error {
+                    p { class: "text-red-400", "{e}" }
+                }
+                if !request.read().clip.is_empty() {
+                    CheckboxInput {
+                        label: "精确剪切（重新编码切点，较慢）".to_string(),
+                        checked: request.read().clip.precise,
+                        on_change: move |value: bool| request.write().clip.precise = value,
+                    }
+                }
+                div {
+                    class: "flex items-center justify-between gap-4",
+                    span { class: "text-gray-300", "限速" }
+                    input {
+                        class: "w-24 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                        placeholder: "按设置",
+                        value: request.read().rate_limit_override.clone().unwrap_or_default(),
+                        oninput: move |e| {
+                            let value = e.value();
+                            request.write().rate_limit_override = (!value.trim().is_empty()).then_some(value);
+                        },
+                    }
+                }
+                if let Some(e) = rate_error {
+                    p { class: "text-red-400", "{e}" }
+                }
+                div {
+                    class: "flex items-center justify-between gap-4",
+                    span { class: "text-gray-300", "定时开始" }
+                    div {
+                        class: "flex items-center gap-2",
+                        input {
+                            r#type: "datetime-local",
+                            class: "bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                            value: format_start_at(request.read().start_at),
+                            onchange: move |e| request.write().start_at = parse_start_at(&e.value()),
+                        }
+                        if request.read().start_at.is_some() {
+                            button {
+                                class: "text-gray-400 hover:text-white",
+                                onclick: move |_| request.write().start_at = None,
+                                "立即"
+                            }
+                        }
+                    }
+                }
+                CheckboxInput {
+                    label: "不受下载时段限制".to_string(),
+                    checked: request.read().ignore_window,
+                    on_change: move |value: bool| request.write().ignore_window = value,
+                }
+                CheckboxInput {
+                    label: "播放列表跳过下载存档中已有的视频".to_string(),
+                    checked: request.read().use_archive,
+                    on_change: move |value: bool| request.write().use_archive = value,
+                }
+                CheckboxInput {
+                    label: "按章节拆分成多个文件".to_string(),
+                    checked: request.read().split_chapters,
+                    on_change: move |value: bool| request.write().split_chapters = value,
+                }
+                CheckboxInput {
+                    label: "下载字幕".to_string(),
+                    checked: request.read().subtitles.write_subs,
+                    on_change: move |value: bool| request.write().subtitles.write_subs = value,
+                }
+                CheckboxInput {
+                    label: "下载自动生成的字幕".to_string(),
+                    checked: request.read().subtitles.write_auto_subs,
+                    on_change: move |value: bool| request.write().subtitles.write_auto_subs = value,
+                }
+                if request.read().subtitles.enabled() && !audio_only {
+                    CheckboxInput {
+                        label: "嵌入字幕".to_string(),
+                        checked: request.read().subtitles.embed,
+                        on_change: move |value: bool| request.write().subtitles.embed = value,
+                    }
+                }
+                CheckboxInput {
+                    label: "嵌入封面".to_string(),
+                    checked: request.read().embed.thumbnail,
+                    on_change: move |value: bool| request.write().embed.thumbnail = value,
+                }
+                CheckboxInput {
+                    label: "嵌入元数据".to_string(),
+                    checked: request.read().embed.metadata,
+                    on_change: move |value: bool| request.write().embed.metadata = value,
+                }
+                CheckboxInput {
+                    label: "嵌入章节".to_string(),
+                    checked: request.read().embed.chapters,
+                    on_change: move |value: bool| request.write().embed.chapters = value,
+                }
+            }
+        }
+    }
+}
+
+/// 一行下拉选择
+#[component]
+fn OptionRow(
+    label: String,
+    options: Vec<(&'static str, &'static str)>,
+    value: String,
+    on_change: EventHandler<String>,
+) -> Element {
+    rsx! {
+        div {
+            class: "flex items-center justify-between gap-4",
+            span { class: "text-gray-300", "{label}" }
+            select {
+                class: "bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                onchange: move |e| on_change.call(e.value()),
+                for (option_value, text) in options {
+                    option {
+                        value: "{option_value}",
+                        selected: value == option_value,
+                        "{text}"
+                    }
+                }
+            }
+        }
+    }
+}
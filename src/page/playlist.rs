@@ -0,0 +1,136 @@
+use crate::downloader::metadata::PlaylistEntry;
+use crate::downloader::playlist::{format_ranges, parse_ranges};
+use crate::downloader::progress::format_duration;
+use crate::downloader::DownloadRequest;
+use crate::page::setting::CheckboxInput;
+use dioxus::prelude::*;
+use std::collections::BTreeSet;
+
+/// 播放列表条目选择，`selected` 中的序号从 1 开始
+#[component]
+pub fn PlaylistPicker(
+    entries: Vec<PlaylistEntry>,
+    selected: Signal<BTreeSet<usize>>,
+    request: Signal<DownloadRequest>,
+) -> Element {
+    let count = entries.len();
+    let mut range_text = use_signal(String::new);
+    let mut range_error = use_signal(|| None::<String>);
+    let mut newest = use_signal(|| 10usize);
+
+    let apply_range = move |_| match parse_ranges(&range_text.read(), count) {
+        Ok(items) => {
+            selected.set(items);
+            range_error.set(None);
+        }
+        Err(e) => range_error.set(Some(e)),
+    };
+
+    rsx! {
+        details {
+            class: "mt-4",
+            open: true,
+            summary {
+                class: "text-sm text-gray-400 cursor-pointer hover:text-white",
+                {format!("播放列表条目（已选 {} / {}）", selected.read().len(), count)}
+            }
+            div {
+                class: "mt-3 space-y-2 text-sm",
+
+                div {
+                    class: "flex flex-wrap items-center gap-2",
+                    button {
+                        class: "px-2 py-1 rounded bg-gray-700 text-gray-300 hover:bg-gray-600",
+                        onclick: move |_| selected.set((1..=count).collect()),
+                        "全选"
+                    }
+                    button {
+                        class: "px-2 py-1 rounded bg-gray-700 text-gray-300 hover:bg-gray-600",
+                        onclick: move |_| selected.set(BTreeSet::new()),
+                        "全不选"
+                    }
+                    input {
+                        class: "w-32 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                        placeholder: "如 1-10,15",
+                        value: "{range_text}",
+                        oninput: move |e| range_text.set(e.value()),
+                    }
+                    button {
+                        class: "px-2 py-1 rounded bg-gray-700 text-gray-300 hover:bg-gray-600",
+                        onclick: apply_range,
+                        "按范围选择"
+                    }
+                    input {
+                        r#type: "number",
+                        min: "1",
+                        class: "w-16 ml-2 bg-gray-700 text-white rounded-lg px-2 py-1 border border-gray-600 focus:border-blue-500",
+                        value: "{newest}",
+                        oninput: move |e| {
+                            if let Ok(n) = e.value().parse() {
+                                newest.set(n);
+                            }
+                        },
+                    }
+                    button {
+                        class: "px-2 py-1 rounded bg-gray-700 text-gray-300 hover:bg-gray-600",
+                        title: "频道与大多数播放列表按从新到旧排列，即选择前 N 项",
+                        onclick: move |_| selected.set((1..=newest().min(count)).collect()),
+                        {format!("选择最新 {} 个", newest())}
+                    }
+                }
+                if let Some(e) = range_error.read().as_ref() {
+                    p { class: "text-red-400", "{e}" }
+                }
+                CheckboxInput {
+                    label: "倒序下载".to_string(),
+                    checked: request.read().playlist_reverse,
+                    on_change: move |value: bool| request.write().playlist_reverse = value,
+                }
+
+                div {
+                    class: "max-h-[240px] overflow-y-auto rounded-lg border border-gray-600",
+                    for (i, entry) in entries.into_iter().enumerate() {
+                        {
+                            let index = i + 1;
+                            let checked = selected.read().contains(&index);
+                            rsx! {
+                                label {
+                                    key: "{index}",
+                                    class: "flex items-center gap-3 px-3 py-1 border-t border-gray-700 first:border-t-0 hover:bg-gray-700 cursor-pointer",
+                                    input {
+                                        r#type: "checkbox",
+                                        class: "accent-blue-600",
+                                        checked,
+                                        onchange: move |e| {
+                                            if e.checked() {
+                                                selected.write().insert(index);
+                                            } else {
+                                                selected.write().remove(&index);
+                                            }
+                                        },
+                                    }
+                                    span { class: "w-8 text-right text-gray-500", "{index}" }
+                                    span {
+                                        class: "flex-1 truncate text-gray-200",
+                                        {entry.title.clone().or(entry.id.clone()).unwrap_or_default()}
+                                    }
+                                    span {
+                                        class: "text-gray-400",
+                                        {entry.duration.map(|d| format_duration(d as u64)).unwrap_or_default()}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                p {
+                    class: "text-gray-400",
+                    {
+                        let items = format_ranges(&selected.read());
+                        if items.is_empty() { "未选择任何条目".to_string() } else { format!("将下载: {}", items) }
+                    }
+                }
+            }
+        }
+    }
+}
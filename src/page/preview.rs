@@ -0,0 +1,53 @@
+use crate::downloader::metadata::VideoInfo;
+use crate::downloader::progress::format_duration;
+use dioxus::prelude::*;
+
+/// 下载前的视频信息预览：封面、标题、作者、时长、上传日期，播放列表显示条目数
+#[component]
+pub fn PreviewCard(info: VideoInfo) -> Element {
+    let facts = [
+        info.uploader_name(),
+        info.duration.map(|d| format_duration(d as u64)),
+        info.upload_date_label().map(|d| format!("上传于 {}", d)),
+        info.is_playlist()
+            .then(|| format!("播放列表 · 共 {} 个视频", info.entry_count())),
+        (!info.chapters.is_empty()).then(|| format!("共 {} 个章节", info.chapters.len())),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    rsx! {
+        div {
+            class: "flex gap-4",
+
+            if let Some(thumbnail) = info.thumbnail_url() {
+                img {
+                    class: "w-48 aspect-video object-cover rounded-lg bg-gray-700 flex-shrink-0",
+                    src: "{thumbnail}",
+                }
+            }
+
+            div {
+                class: "min-w-0 flex-1 space-y-2",
+                h2 {
+                    class: "text-lg font-semibold text-white line-clamp-2",
+                    title: info.title.clone().unwrap_or_default(),
+                    {info.title.clone().unwrap_or_else(|| "未知标题".to_string())}
+                }
+                for fact in facts {
+                    p {
+                        class: "text-sm text-gray-400",
+                        "{fact}"
+                    }
+                }
+                if let Some(site) = info.extractor_key.as_ref() {
+                    span {
+                        class: "inline-block px-2 py-0.5 rounded text-xs text-gray-300 bg-gray-700",
+                        "{site}"
+                    }
+                }
+            }
+        }
+    }
+}
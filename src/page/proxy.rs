@@ -0,0 +1,77 @@
+use crate::downloader::proxy;
+use crate::page::run_blocking;
+use dioxus::prelude::*;
+
+/// 代理设置：地址校验通过才保存，可以测试代理能否转发
+#[component]
+pub fn ProxyEditor(value: String, on_save: EventHandler<String>) -> Element {
+    let mut draft = use_signal(|| value.clone());
+    let mut testing = use_signal(|| false);
+    let mut test_result = use_signal(|| None::<Result<String, String>>);
+    let validation = match draft.read().trim() {
+        "" => Ok(()),
+        url => proxy::parse(url).map(|_| ()),
+    };
+    let changed = draft.read().trim() != value;
+
+    let test_proxy = move |_| {
+        let url = draft.read().trim().to_string();
+        testing.set(true);
+        test_result.set(None);
+        spawn(async move {
+            let result = run_blocking(move || proxy::test(&url)).await;
+            test_result.set(Some(result));
+            testing.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+            h2 {
+                class: "text-lg font-semibold mb-3 text-white",
+                "代理"
+            }
+            input {
+                class: "w-full bg-gray-700 text-white rounded-lg px-3 py-2 border border-gray-600 focus:border-blue-500 font-mono text-sm",
+                placeholder: "不使用代理",
+                value: "{draft}",
+                oninput: move |e| {
+                    draft.set(e.value());
+                    test_result.set(None);
+                },
+            }
+            if let Err(e) = &validation {
+                p { class: "mt-1 text-sm text-red-400", "{e}" }
+            }
+            div {
+                class: "mt-3 flex items-center gap-2",
+                button {
+                    class: "bg-blue-600 hover:bg-blue-700 text-white px-4 py-1 rounded-lg transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: validation.is_err() || !changed,
+                    onclick: move |_| on_save.call(draft.read().trim().to_string()),
+                    "保存"
+                }
+                button {
+                    class: "bg-gray-700 hover:bg-gray-600 text-white px-4 py-1 rounded-lg transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: validation.is_err() || draft.read().trim().is_empty() || *testing.read(),
+                    onclick: test_proxy,
+                    if *testing.read() { "测试中..." } else { "测试代理" }
+                }
+                match &*test_result.read() {
+                    Some(Ok(message)) => rsx! {
+                        span { class: "text-sm text-green-400", "{message}" }
+                    },
+                    Some(Err(e)) => rsx! {
+                        span { class: "text-sm text-red-400", "{e}" }
+                    },
+                    None => rsx! {},
+                }
+            }
+            p {
+                class: "mt-2 text-sm text-gray-400",
+                "支持 http、https、socks4、socks5，如 socks5://127.0.0.1:1080；留空时 yt-dlp 使用系统环境变量中的代理。站点规则可以单独指定代理"
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::downloader::schedule::parse_time;
+use crate::downloader::{RateLimit, RateProfile};
+use chrono::Local;
+use dioxus::prelude::*;
+
+/// 全局限速与按时段的限速方案
+#[component]
+pub fn RateLimitEditor(value: RateLimit, on_change: EventHandler<RateLimit>) -> Element {
+    let error = value.validate().err();
+    let current = match value.current(Local::now().time()).trim() {
+        "" => "不限速".to_string(),
+        limit => format!("{}/s", limit),
+    };
+
+    let update = {
+        let value = value.clone();
+        move |index: usize, profile: Option<RateProfile>| {
+            let mut value = value.clone();
+            match profile {
+                Some(profile) => value.profiles[index] = profile,
+                None => {
+                    value.profiles.remove(index);
+                }
+            }
+            on_change.call(value);
+        }
+    };
+
+    let add_profile = {
+        let value = value.clone();
+        move |_| {
+            let mut value = value.clone();
+            value.profiles.push(RateProfile::default());
+            on_change.call(value);
+        }
+    };
+
+    rsx! {
+        div {
+            class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+            div {
+                class: "flex justify-between items-center mb-3",
+                h2 {
+                    class: "text-lg font-semibold text-white",
+                    "限速"
+                }
+                button {
+                    class: "text-sm text-gray-400 hover:text-white transition-colors",
+                    onclick: add_profile,
+                    "添加时段"
+                }
+            }
+
+            div {
+                class: "space-y-2 text-sm",
+                div {
+                    class: "flex items-center justify-between gap-4",
+                    span { class: "text-gray-300", "默认限速" }
+                    input {
+                        class: "w-28 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500 font-mono",
+                        placeholder: "不限速",
+                        value: "{value.limit}",
+                        onchange: {
+                            let value = value.clone();
+                            move |e: FormEvent| on_change.call(RateLimit { limit: e.value().trim().to_string(), ..value.clone() })
+                        },
+                    }
+                }
+                for (index, profile) in value.profiles.iter().cloned().enumerate() {
+                    ProfileItem {
+                        key: "{index}",
+                        profile,
+                        on_change: {
+                            let update = update.clone();
+                            move |profile: Option<RateProfile>| update(index, profile)
+                        },
+                    }
+                }
+            }
+
+            if let Some(e) = error {
+                p { class: "mt-2 text-sm text-red-400", "{e}" }
+            }
+            p {
+                class: "mt-2 text-sm text-gray-400",
+                {format!("单位为字节/秒，如 500K、2M，按 1024 换算；Mb 等比特单位不可用。时段按顺序匹配，切换时段时正在下载的任务会断点续传重新开始。当前：{}", current)}
+            }
+        }
+    }
+}
+
+/// 单个时段方案，`on_change(None)` 表示删除
+#[component]
+fn ProfileItem(profile: RateProfile, on_change: EventHandler<Option<RateProfile>>) -> Element {
+    let edit = {
+        let profile = profile.clone();
+        move |apply: fn(&mut RateProfile, String), value: String| {
+            let mut profile = profile.clone();
+            apply(&mut profile, value);
+            on_change.call(Some(profile));
+        }
+    };
+
+    rsx! {
+        div {
+            class: "flex items-center gap-2 bg-gray-700 rounded-lg px-3 py-2",
+            input {
+                r#type: "checkbox",
+                class: "w-4 h-4",
+                checked: profile.enabled,
+                onchange: {
+                    let profile = profile.clone();
+                    move |e: FormEvent| {
+                        on_change.call(Some(RateProfile { enabled: e.checked(), ..profile.clone() }))
+                    }
+                },
+            }
+            input {
+                r#type: "time",
+                class: "bg-gray-800 text-white rounded-lg px-2 py-1 border border-gray-600 focus:border-blue-500",
+                value: "{profile.start}",
+                onchange: {
+                    let edit = edit.clone();
+                    move |e: FormEvent| {
+                        if parse_time(&e.value()).is_some() {
+                            edit(|p, v| p.start = v, e.value());
+                        }
+                    }
+                },
+            }
+            span { class: "text-gray-400", "-" }
+            input {
+                r#type: "time",
+                class: "bg-gray-800 text-white rounded-lg px-2 py-1 border border-gray-600 focus:border-blue-500",
+                value: "{profile.end}",
+                onchange: {
+                    let edit = edit.clone();
+                    move |e: FormEvent| {
+                        if parse_time(&e.value()).is_some() {
+                            edit(|p, v| p.end = v, e.value());
+                        }
+                    }
+                },
+            }
+            input {
+                class: "flex-1 min-w-0 bg-gray-800 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500 font-mono",
+                placeholder: "不限速",
+                value: "{profile.limit}",
+                onchange: {
+                    let edit = edit.clone();
+                    move |e: FormEvent| edit(|p, v| p.limit = v.trim().to_string(), e.value())
+                },
+            }
+            button {
+                class: "text-gray-400 hover:text-red-400 transition-colors",
+                onclick: move |_| on_change.call(None),
+                "删除"
+            }
+        }
+    }
+}
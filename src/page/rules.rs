@@ -0,0 +1,356 @@
+use crate::downloader::SiteRule;
+use crate::page::setting::CheckboxInput;
+use dioxus::prelude::*;
+
+/// 站点规则列表：按顺序匹配，第一条匹配的规则生效
+#[component]
+pub fn SiteRulesEditor(rules: Vec<SiteRule>, on_change: EventHandler<Vec<SiteRule>>) -> Element {
+    let count = rules.len();
+
+    let update = {
+        let rules = rules.clone();
+        move |index: usize, rule: Option<SiteRule>| {
+            let mut rules = rules.clone();
+            match rule {
+                Some(rule) => rules[index] = rule,
+                None => {
+                    rules.remove(index);
+                }
+            }
+            on_change.call(rules);
+        }
+    };
+
+    let move_up = {
+        let rules = rules.clone();
+        move |index: usize| {
+            let mut rules = rules.clone();
+            rules.swap(index - 1, index);
+            on_change.call(rules);
+        }
+    };
+
+    let add_rule = {
+        let rules = rules.clone();
+        move |_| {
+            let mut rules = rules.clone();
+            rules.insert(
+                0,
+                SiteRule {
+                    name: "新规则".to_string(),
+                    enabled: true,
+                    ..Default::default()
+                },
+            );
+            on_change.call(rules);
+        }
+    };
+
+    rsx! {
+        div {
+            class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+            div {
+                class: "flex justify-between items-center mb-3",
+                h2 {
+                    class: "text-lg font-semibold text-white",
+                    "站点规则"
+                }
+                div {
+                    class: "flex gap-3 text-sm",
+                    button {
+                        class: "text-gray-400 hover:text-white transition-colors",
+                        onclick: add_rule,
+                        "添加"
+                    }
+                    button {
+                        class: "text-gray-400 hover:text-white transition-colors",
+                        onclick: move |_| on_change.call(SiteRule::defaults()),
+                        "恢复默认"
+                    }
+                }
+            }
+
+            div {
+                class: "space-y-2",
+                for (index, rule) in rules.into_iter().enumerate() {
+                    RuleItem {
+                        key: "{index}",
+                        rule,
+                        can_move_up: index > 0,
+                        on_change: {
+                            let update = update.clone();
+                            move |rule: Option<SiteRule>| update(index, rule)
+                        },
+                        on_move_up: {
+                            let move_up = move_up.clone();
+                            move |_| move_up(index)
+                        },
+                    }
+                }
+            }
+
+            p {
+                class: "mt-2 text-sm text-gray-400",
+                {format!("共 {} 条规则，按顺序匹配，第一条匹配的规则生效", count)}
+            }
+        }
+    }
+}
+
+/// 单条规则，`on_change(None)` 表示删除。
+/// 校验不通过的修改只保留在本地草稿中，不会保存到设置
+#[component]
+fn RuleItem(
+    rule: SiteRule,
+    can_move_up: bool,
+    on_change: EventHandler<Option<SiteRule>>,
+    on_move_up: EventHandler<()>,
+) -> Element {
+    // (草稿基于的规则, 草稿)；外部规则变化后旧草稿作废
+    let mut draft = use_signal(|| None::<(SiteRule, SiteRule)>);
+    let pending = draft
+        .read()
+        .as_ref()
+        .filter(|(base, _)| *base == rule)
+        .map(|(_, draft)| draft.clone());
+    let has_draft = pending.is_some();
+    let current = pending.unwrap_or_else(|| rule.clone());
+    let error = current.validate().err();
+
+    // 修改后的规则校验通过才保存
+    let save = {
+        let rule = rule.clone();
+        move |mut edited: SiteRule| {
+            let mut draft = draft;
+            if edited.validate().is_ok() {
+                draft.set(None);
+                edited.retries_text = None;
+                on_change.call(Some(edited));
+            } else {
+                draft.set(Some((rule.clone(), edited)));
+            }
+        }
+    };
+
+    // 修改某个字段后整条规则一起保存
+    let edit = {
+        let current = current.clone();
+        let save = save.clone();
+        move |apply: fn(&mut SiteRule, String), value: String| {
+            let mut rule = current.clone();
+            apply(&mut rule, value);
+            save(rule);
+        }
+    };
+    let rule = current;
+
+    rsx! {
+        details {
+            class: "bg-gray-700 rounded-lg p-3",
+            summary {
+                class: "flex items-center gap-3 cursor-pointer",
+                span {
+                    class: if rule.enabled { "flex-1 text-white" } else { "flex-1 text-gray-500 line-through" },
+                    "{rule.name}"
+                }
+                if has_draft {
+                    span { class: "text-xs text-red-400", "有错误，未保存" }
+                } else if error.is_some() {
+                    span { class: "text-xs text-red-400", "有错误" }
+                }
+                if can_move_up {
+                    button {
+                        class: "text-xs text-gray-400 hover:text-white",
+                        onclick: move |e| {
+                            e.prevent_default();
+                            on_move_up.call(());
+                        },
+                        "上移"
+                    }
+                }
+            }
+
+            div {
+                class: "mt-3 space-y-2 text-sm",
+                CheckboxInput {
+                    label: "启用".to_string(),
+                    checked: rule.enabled,
+                    on_change: {
+                        let rule = rule.clone();
+                        let save = save.clone();
+                        move |enabled: bool| save(SiteRule { enabled, ..rule.clone() })
+                    },
+                }
+                RuleField {
+                    label: "名称".to_string(),
+                    value: rule.name.clone(),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| edit(|r, v| r.name = v, v)
+                    },
+                }
+                RuleField {
+                    label: "域名（逗号分隔）".to_string(),
+                    value: rule.domains.join(", "),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| {
+                            edit(
+                                |r, v| {
+                                    r.domains = v
+                                        .split(',')
+                                        .map(|d| d.trim().to_string())
+                                        .filter(|d| !d.is_empty())
+                                        .collect()
+                                },
+                                v,
+                            )
+                        }
+                    },
+                }
+                RuleField {
+                    label: "链接正则".to_string(),
+                    value: rule.pattern.clone(),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| edit(|r, v| r.pattern = v, v)
+                    },
+                }
+                RuleField {
+                    label: "最佳画质格式".to_string(),
+                    value: rule.format_best.clone(),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| edit(|r, v| r.format_best = v, v)
+                    },
+                }
+                RuleField {
+                    label: "普通画质格式".to_string(),
+                    value: rule.format_normal.clone(),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| edit(|r, v| r.format_normal = v, v)
+                    },
+                }
+                RuleField {
+                    label: "附加参数".to_string(),
+                    value: rule.extra_args.clone(),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| edit(|r, v| r.extra_args = v, v)
+                    },
+                }
+                div {
+                    p { class: "text-gray-300 mb-1", "请求头（每行一个，Name: Value）" }
+                    textarea {
+                        class: "w-full bg-gray-800 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500 font-mono",
+                        rows: "2",
+                        value: rule.headers.join("\n"),
+                        onchange: {
+                            let edit = edit.clone();
+                            move |e: FormEvent| {
+                                edit(
+                                    |r, v| {
+                                        r.headers = v
+                                            .lines()
+                                            .map(|h| h.trim().to_string())
+                                            .filter(|h| !h.is_empty())
+                                            .collect()
+                                    },
+                                    e.value(),
+                                )
+                            }
+                        },
+                    }
+                }
+                RuleField {
+                    label: "子文件夹".to_string(),
+                    value: rule.subfolder.clone(),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| edit(|r, v| r.subfolder = v, v)
+                    },
+                }
+                RuleField {
+                    label: "重试次数（留空使用全局设置）".to_string(),
+                    value: rule
+                        .retries_text
+                        .clone()
+                        .unwrap_or_else(|| rule.retries.map(|r| r.to_string()).unwrap_or_default()),
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| {
+                            edit(
+                                |r, v| {
+                                    r.retries = v.trim().parse().ok();
+                                    r.retries_text = Some(v);
+                                },
+                                v,
+                            )
+                        }
+                    },
+                }
+                RuleField {
+                    label: "代理（留空使用全局设置，direct 表示直连）".to_string(),
+                    value: match rule.proxy.as_deref() {
+                        Some("") => "direct".to_string(),
+                        proxy => proxy.unwrap_or_default().to_string(),
+                    },
+                    on_change: {
+                        let edit = edit.clone();
+                        move |v: String| {
+                            edit(
+                                |r, v| {
+                                    r.proxy = match v.trim() {
+                                        "" => None,
+                                        "direct" => Some(String::new()),
+                                        proxy => Some(proxy.to_string()),
+                                    }
+                                },
+                                v,
+                            )
+                        }
+                    },
+                }
+
+                if let Some(e) = error {
+                    p { class: "text-red-400", "{e}" }
+                }
+
+                div {
+                    class: "flex gap-4",
+                    if has_draft {
+                        button {
+                            class: "text-gray-400 hover:text-white transition-colors",
+                            onclick: move |_| draft.set(None),
+                            "放弃修改"
+                        }
+                    }
+                    button {
+                        class: "text-red-400 hover:text-red-300 transition-colors",
+                        onclick: move |_| {
+                            draft.set(None);
+                            on_change.call(None);
+                        },
+                        "删除规则"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RuleField(label: String, value: String, on_change: EventHandler<String>) -> Element {
+    rsx! {
+        div {
+            class: "flex items-center justify-between gap-4",
+            span { class: "text-gray-300 flex-shrink-0", "{label}" }
+            input {
+                class: "flex-1 min-w-0 bg-gray-800 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500 font-mono",
+                value: "{value}",
+                onchange: move |e| on_change.call(e.value()),
+            }
+        }
+    }
+}
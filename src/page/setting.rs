@@ -1,11 +1,21 @@
 use crate::db::Settings;
+use crate::downloader::schedule::parse_time;
+use crate::downloader::DownloadQueue;
+use crate::page::archive::ArchivePanel;
+use crate::page::options::{AUDIO_FORMATS, AUDIO_QUALITIES, SUBTITLE_FORMATS};
+use crate::page::proxy::ProxyEditor;
+use crate::page::ratelimit::RateLimitEditor;
+use crate::page::rules::SiteRulesEditor;
+use crate::page::subscriptions::SubscriptionPanel;
+use crate::page::template::TemplateEditor;
 use dioxus::prelude::*;
 use rfd::AsyncFileDialog;
 
 #[component]
 pub fn Setting() -> Element {
     // 使用 Settings 结构体来管理设置
-    let settings = use_signal(Settings::load);
+    let mut settings = use_signal(Settings::load);
+    let queue = use_context::<DownloadQueue>();
 
     // 选项配置
     let quality_options = vec![
@@ -18,6 +28,62 @@ pub fn Setting() -> Element {
         ("no".to_string(), "不下载视频列表".to_string()),
     ];
 
+    let download_mode_options = vec![
+        ("video".to_string(), "视频".to_string()),
+        ("audio".to_string(), "仅音频".to_string()),
+    ];
+
+    let audio_format_options = AUDIO_FORMATS
+        .map(|(value, text)| (value.to_string(), text.to_string()))
+        .to_vec();
+
+    let audio_quality_options = AUDIO_QUALITIES
+        .map(|(value, text)| (value.to_string(), text.to_string()))
+        .to_vec();
+
+    let playlist_folder_options = vec![
+        (
+            "yes".to_string(),
+            "放进播放列表文件夹并生成 .m3u8".to_string(),
+        ),
+        ("no".to_string(), "直接保存到下载目录".to_string()),
+    ];
+
+    let archive_options = vec![
+        ("yes".to_string(), "播放列表跳过已下载的视频".to_string()),
+        ("no".to_string(), "总是重新下载".to_string()),
+    ];
+
+    let clean_partial_options = vec![
+        ("yes".to_string(), "删除临时文件".to_string()),
+        ("no".to_string(), "保留临时文件".to_string()),
+    ];
+
+    let backoff_options = [
+        ("fixed", "固定间隔"),
+        ("linear", "线性增长"),
+        ("exp", "指数增长"),
+    ];
+
+    // 与所选的等待增长方式对应的说明
+    let retry_help = {
+        let retry = &settings.read().retry;
+        let sleep = match (retry.retry_sleep, retry.backoff.as_str()) {
+            (0, _) => "网络或分片出错时立即重试".to_string(),
+            (n, "linear") => format!("网络或分片出错时等待 {} 秒后重试，之后每次多等 1 秒", n),
+            (n, "exp") => format!("网络或分片出错时等待 {} 秒后重试，之后每次等待时间翻倍", n),
+            (n, _) => format!("网络或分片出错时每次等待 {} 秒后重试", n),
+        };
+        format!(
+            "{}。整个任务失败后自动重新下载，等待 {} 秒起，每次翻倍",
+            sleep, retry.job_retry_delay
+        )
+    };
+
+    let concurrency_options = (1..=5)
+        .map(|n| (n.to_string(), format!("{} 个", n)))
+        .collect::<Vec<_>>();
+
     // 选择文件夹的函数
     let select_folder = {
         let mut settings = settings.clone();
@@ -55,6 +121,47 @@ pub fn Setting() -> Element {
                 },
             }
 
+            // 下载模式：视频或仅音频
+            Dropdown {
+                title: "下载内容".to_string(),
+                options: download_mode_options,
+                selected_value: Signal::new(settings.read().download_mode.clone()),
+                on_change: {
+                    let mut settings = settings;
+                    Callback::new(move |value: String| {
+                        settings.write().download_mode = value;
+                        settings.read().save();
+                    })
+                },
+            }
+
+            if settings.read().download_mode == "audio" {
+                Dropdown {
+                    title: "音频格式".to_string(),
+                    options: audio_format_options,
+                    selected_value: Signal::new(settings.read().audio_format.clone()),
+                    on_change: {
+                        let mut settings = settings;
+                        Callback::new(move |value: String| {
+                            settings.write().audio_format = value;
+                            settings.read().save();
+                        })
+                    },
+                }
+                Dropdown {
+                    title: "音频质量".to_string(),
+                    options: audio_quality_options,
+                    selected_value: Signal::new(settings.read().audio_quality.clone()),
+                    on_change: {
+                        let mut settings = settings;
+                        Callback::new(move |value: String| {
+                            settings.write().audio_quality = value;
+                            settings.read().save();
+                        })
+                    },
+                }
+            }
+
             // 视频列表下载选择
             Dropdown {
                 title: "视频列表".to_string(),
@@ -69,6 +176,356 @@ pub fn Setting() -> Element {
                 },
             }
 
+            // 播放列表文件夹
+            Dropdown {
+                title: "播放列表保存方式".to_string(),
+                options: playlist_folder_options,
+                selected_value: Signal::new(settings.read().playlist_folder.clone()),
+                on_change: {
+                    let mut settings = settings;
+                    Callback::new(move |value: String| {
+                        settings.write().playlist_folder = value;
+                        settings.read().save();
+                    })
+                },
+            }
+
+            // 同时下载数
+            Dropdown {
+                title: "同时下载数".to_string(),
+                options: concurrency_options,
+                selected_value: Signal::new(settings.read().max_concurrent_downloads.to_string()),
+                on_change: {
+                    let mut settings = settings;
+                    let queue = queue.clone();
+                    Callback::new(move |value: String| {
+                        let max = value.parse().unwrap_or(1);
+                        settings.write().max_concurrent_downloads = max;
+                        settings.read().save();
+                        queue.set_max_concurrent(max);
+                    })
+                },
+            }
+
+            // 下载时段
+            div {
+                class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+                h2 {
+                    class: "text-lg font-semibold mb-3 text-white",
+                    "下载时段"
+                }
+                CheckboxInput {
+                    label: "只在指定时段内开始下载".to_string(),
+                    checked: settings.read().download_window.enabled,
+                    on_change: {
+                        let queue = queue.clone();
+                        move |value: bool| {
+                            settings.write().download_window.enabled = value;
+                            settings.read().save();
+                            queue.set_window(settings.read().download_window.clone());
+                        }
+                    },
+                }
+                div {
+                    class: "flex items-center justify-between gap-4 mb-2",
+                    span { class: "text-gray-300", "时段" }
+                    div {
+                        class: "flex items-center gap-2",
+                        input {
+                            r#type: "time",
+                            class: "bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                            value: "{settings.read().download_window.start}",
+                            onchange: {
+                                let queue = queue.clone();
+                                move |e: Event<FormData>| {
+                                    if parse_time(&e.value()).is_none() {
+                                        return;
+                                    }
+                                    settings.write().download_window.start = e.value();
+                                    settings.read().save();
+                                    queue.set_window(settings.read().download_window.clone());
+                                }
+                            },
+                        }
+                        span { class: "text-gray-400", "-" }
+                        input {
+                            r#type: "time",
+                            class: "bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                            value: "{settings.read().download_window.end}",
+                            onchange: {
+                                let queue = queue.clone();
+                                move |e: Event<FormData>| {
+                                    if parse_time(&e.value()).is_none() {
+                                        return;
+                                    }
+                                    settings.write().download_window.end = e.value();
+                                    settings.read().save();
+                                    queue.set_window(settings.read().download_window.clone());
+                                }
+                            },
+                        }
+                    }
+                }
+                p {
+                    class: "mt-2 text-sm text-gray-400",
+                    "时段外的任务会排队等待，时段结束时正在下载的任务会暂停，下个时段开始后自动继续；结束时间早于开始时间表示跨过午夜"
+                }
+            }
+
+            // 取消下载时的临时文件处理
+            Dropdown {
+                title: "取消下载时".to_string(),
+                options: clean_partial_options,
+                selected_value: Signal::new(settings.read().clean_partial_on_cancel.clone()),
+                on_change: {
+                    let mut settings = settings;
+                    Callback::new(move |value: String| {
+                        settings.write().clean_partial_on_cancel = value;
+                        settings.read().save();
+                    })
+                },
+            }
+
+            // 代理
+            ProxyEditor {
+                value: settings.read().proxy.clone(),
+                on_save: move |value: String| {
+                    settings.write().proxy = value;
+                    settings.read().save();
+                },
+            }
+
+            // 限速
+            RateLimitEditor {
+                value: settings.read().rate_limit.clone(),
+                on_change: move |value| {
+                    settings.write().rate_limit = value;
+                    settings.read().save();
+                },
+            }
+
+            // 重试策略
+            div {
+                class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+                h2 {
+                    class: "text-lg font-semibold mb-3 text-white",
+                    "重试策略"
+                }
+                NumberInput {
+                    label: "网络错误重试次数".to_string(),
+                    value: settings.read().retry.retries.into(),
+                    max: u32::MAX.into(),
+                    on_change: move |value: u64| {
+                        settings.write().retry.retries = u32::try_from(value).unwrap_or(u32::MAX);
+                        settings.read().save();
+                    },
+                }
+                NumberInput {
+                    label: "分片重试次数".to_string(),
+                    value: settings.read().retry.fragment_retries.into(),
+                    max: u32::MAX.into(),
+                    on_change: move |value: u64| {
+                        settings.write().retry.fragment_retries = u32::try_from(value).unwrap_or(u32::MAX);
+                        settings.read().save();
+                    },
+                }
+                NumberInput {
+                    label: "重试等待（秒）".to_string(),
+                    value: settings.read().retry.retry_sleep.into(),
+                    max: u32::MAX.into(),
+                    on_change: move |value: u64| {
+                        settings.write().retry.retry_sleep = u32::try_from(value).unwrap_or(u32::MAX);
+                        settings.read().save();
+                    },
+                }
+                div {
+                    class: "flex items-center justify-between gap-4 mb-2",
+                    span { class: "text-gray-300", "等待增长方式" }
+                    select {
+                        class: "bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                        onchange: move |e| {
+                            settings.write().retry.backoff = e.value();
+                            settings.read().save();
+                        },
+                        for (value, text) in backoff_options {
+                            option {
+                                value: "{value}",
+                                selected: settings.read().retry.backoff == value,
+                                "{text}"
+                            }
+                        }
+                    }
+                }
+                NumberInput {
+                    label: "失败任务自动重试次数".to_string(),
+                    value: settings.read().retry.job_retries.into(),
+                    max: u32::MAX.into(),
+                    on_change: move |value: u64| {
+                        settings.write().retry.job_retries = u32::try_from(value).unwrap_or(u32::MAX);
+                        settings.read().save();
+                    },
+                }
+                NumberInput {
+                    label: "自动重试基础间隔（秒）".to_string(),
+                    value: settings.read().retry.job_retry_delay,
+                    on_change: move |value: u64| {
+                        settings.write().retry.job_retry_delay = value;
+                        settings.read().save();
+                    },
+                }
+                p {
+                    class: "mt-2 text-sm text-gray-400",
+                    "{retry_help}"
+                }
+            }
+
+            // 字幕
+            div {
+                class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+                h2 {
+                    class: "text-lg font-semibold mb-3 text-white",
+                    "字幕"
+                }
+                CheckboxInput {
+                    label: "下载字幕".to_string(),
+                    checked: settings.read().subtitles.write_subs,
+                    on_change: move |value: bool| {
+                        settings.write().subtitles.write_subs = value;
+                        settings.read().save();
+                    },
+                }
+                CheckboxInput {
+                    label: "下载自动生成的字幕".to_string(),
+                    checked: settings.read().subtitles.write_auto_subs,
+                    on_change: move |value: bool| {
+                        settings.write().subtitles.write_auto_subs = value;
+                        settings.read().save();
+                    },
+                }
+                div {
+                    class: "flex items-center justify-between gap-4 mb-2",
+                    span { class: "text-gray-300", "首选语言" }
+                    input {
+                        class: "w-40 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                        value: "{settings.read().subtitles.languages}",
+                        onchange: move |e| {
+                            settings.write().subtitles.languages = e.value();
+                            settings.write().subtitles.disable_if_empty();
+                            settings.read().save();
+                        },
+                    }
+                }
+                div {
+                    class: "flex items-center justify-between gap-4 mb-2",
+                    span { class: "text-gray-300", "转换格式" }
+                    select {
+                        class: "bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                        onchange: move |e| {
+                            settings.write().subtitles.convert_format = e.value();
+                            settings.read().save();
+                        },
+                        for (value, text) in SUBTITLE_FORMATS {
+                            option {
+                                value: "{value}",
+                                selected: settings.read().subtitles.convert_format == value,
+                                "{text}"
+                            }
+                        }
+                    }
+                }
+                CheckboxInput {
+                    label: "嵌入到视频文件".to_string(),
+                    checked: settings.read().subtitles.embed,
+                    on_change: move |value: bool| {
+                        settings.write().subtitles.embed = value;
+                        settings.read().save();
+                    },
+                }
+                p {
+                    class: "mt-2 text-sm text-gray-400",
+                    "语言用逗号分隔，支持正则，如 zh.*,en；all 表示全部语言"
+                }
+            }
+
+            // 写入文件的附加信息
+            div {
+                class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+                h2 {
+                    class: "text-lg font-semibold mb-3 text-white",
+                    "嵌入信息"
+                }
+                CheckboxInput {
+                    label: "嵌入封面".to_string(),
+                    checked: settings.read().embed.thumbnail,
+                    on_change: move |value: bool| {
+                        settings.write().embed.thumbnail = value;
+                        settings.read().save();
+                    },
+                }
+                CheckboxInput {
+                    label: "嵌入元数据".to_string(),
+                    checked: settings.read().embed.metadata,
+                    on_change: move |value: bool| {
+                        settings.write().embed.metadata = value;
+                        settings.read().save();
+                    },
+                }
+                CheckboxInput {
+                    label: "嵌入章节".to_string(),
+                    checked: settings.read().embed.chapters,
+                    on_change: move |value: bool| {
+                        settings.write().embed.chapters = value;
+                        settings.read().save();
+                    },
+                }
+                p {
+                    class: "mt-2 text-sm text-gray-400",
+                    "元数据包括标题、作者、上传日期和简介，由 ffmpeg 在下载完成后写入"
+                }
+            }
+
+            // 文件名模板
+            TemplateEditor {
+                value: settings.read().filename_template.clone(),
+                on_save: move |value: String| {
+                    settings.write().filename_template = value;
+                    settings.read().save();
+                },
+            }
+
+            // 站点规则
+            SiteRulesEditor {
+                rules: settings.read().site_rules.clone(),
+                on_change: move |rules: Vec<_>| {
+                    settings.write().site_rules = rules;
+                    settings.read().save();
+                },
+            }
+
+            // 下载存档
+            Dropdown {
+                title: "下载存档".to_string(),
+                options: archive_options,
+                selected_value: Signal::new(settings.read().use_archive.clone()),
+                on_change: {
+                    let mut settings = settings;
+                    Callback::new(move |value: String| {
+                        settings.write().use_archive = value;
+                        settings.read().save();
+                    })
+                },
+            }
+            ArchivePanel {}
+
+            // 订阅
+            SubscriptionPanel {
+                interval: settings.read().subscription_interval,
+                on_interval_change: move |value: u64| {
+                    settings.write().subscription_interval = value.max(1);
+                    settings.read().save();
+                },
+            }
+
             // 下载路径选择
              div {
                  class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
@@ -104,11 +561,27 @@ pub fn Setting() -> Element {
                             {settings.read().quality.clone()}
                         }
                     }
+                    p { class: "text-gray-300",
+                        "下载内容: " span { class: "font-medium text-white",
+                            {
+                                if settings.read().download_mode == "audio" {
+                                    format!("仅音频 ({})", settings.read().audio_format)
+                                } else {
+                                    "视频".to_string()
+                                }
+                            }
+                        }
+                    }
                     p { class: "text-gray-300",
                         "视频列表下载: " span { class: "font-medium text-white",
                             {settings.read().down_video_list.clone()}
                         }
                     }
+                    p { class: "text-gray-300",
+                        "同时下载数: " span { class: "font-medium text-white",
+                            {settings.read().max_concurrent_downloads.to_string()}
+                        }
+                    }
                     p { class: "text-gray-300",
                         "下载路径: " span { class: "font-medium text-white",
                             {settings.read().download_path.clone()}
@@ -175,3 +648,67 @@ pub fn Dropdown(mut props: DropdownProps) -> Element {
         }
     }
 }
+
+/// 设置卡片中的一行数字输入
+#[component]
+pub fn NumberInput(
+    label: String,
+    value: u64,
+    #[props(default = u64::MAX)] max: u64,
+    on_change: EventHandler<u64>,
+) -> Element {
+    let mut error = use_signal(|| None::<String>);
+
+    rsx! {
+        div {
+            class: "mb-2",
+            div {
+                class: "flex items-center justify-between gap-4",
+                span { class: "text-gray-300", "{label}" }
+                input {
+                    r#type: "number",
+                    min: "0",
+                    max: "{max}",
+                    class: "w-24 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500 focus:ring-2 focus:ring-blue-500/50",
+                    value: "{value}",
+                    onchange: move |e| {
+                        let text = e.value();
+                        let text = text.trim();
+                        // 超出范围的整数按最大值处理，其它输入不保存
+                        match text.parse::<u64>() {
+                            Ok(value) if value <= max => {
+                                error.set(None);
+                                on_change.call(value);
+                            }
+                            _ if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) => {
+                                error.set(Some(format!("最大为 {}，已按最大值保存", max)));
+                                on_change.call(max);
+                            }
+                            _ => error.set(Some("请输入非负整数".to_string())),
+                        }
+                    }
+                }
+            }
+            if let Some(e) = &*error.read() {
+                p { class: "mt-1 text-sm text-red-400 text-right", "{e}" }
+            }
+        }
+    }
+}
+
+/// 设置卡片中的一行开关
+#[component]
+pub fn CheckboxInput(label: String, checked: bool, on_change: EventHandler<bool>) -> Element {
+    rsx! {
+        label {
+            class: "flex items-center justify-between gap-4 mb-2 cursor-pointer",
+            span { class: "text-gray-300", "{label}" }
+            input {
+                r#type: "checkbox",
+                class: "w-4 h-4 accent-blue-600",
+                checked,
+                onchange: move |e| on_change.call(e.checked()),
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::db::{HistoryDb, Subscription};
+use crate::downloader::{subscriptions, DownloadQueue};
+use crate::page::history::format_time;
+use crate::page::run_blocking;
+use crate::page::setting::NumberInput;
+use dioxus::prelude::*;
+use rfd::AsyncFileDialog;
+
+fn load_subscriptions() -> Result<Vec<Subscription>, String> {
+    HistoryDb::open()
+        .and_then(|history| history.subscriptions())
+        .map_err(|e| format!("读取订阅失败: {}", e))
+}
+
+/// 修改订阅后写回数据库
+fn save_subscription(subscription: &Subscription) -> Option<String> {
+    HistoryDb::open()
+        .and_then(|history| history.update_subscription(subscription))
+        .err()
+        .map(|e| format!("保存订阅失败: {}", e))
+}
+
+/// 订阅的频道与播放列表
+#[component]
+pub fn SubscriptionPanel(interval: u64, on_interval_change: EventHandler<u64>) -> Element {
+    let mut subscriptions = use_signal(load_subscriptions);
+    let mut new_url = use_signal(String::new);
+    let mut message = use_signal(|| None::<String>);
+
+    let mut reload = move || subscriptions.set(load_subscriptions());
+
+    let add = move |_| {
+        let url = new_url.read().trim().to_string();
+        if url.is_empty() {
+            return;
+        }
+        let result = HistoryDb::open().and_then(|history| history.add_subscription(&url, None));
+        match result {
+            Ok(_) => {
+                new_url.set(String::new());
+                message.set(Some(
+                    "已添加订阅，首次检查只记录现有视频，之后的新视频会自动下载".to_string(),
+                ));
+            }
+            Err(e) => message.set(Some(format!("添加订阅失败: {}", e))),
+        }
+        reload();
+    };
+
+    rsx! {
+        div {
+            class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+            h2 {
+                class: "text-lg font-semibold mb-3 text-white",
+                "订阅"
+            }
+            NumberInput {
+                label: "检查间隔（分钟）".to_string(),
+                value: interval,
+                on_change: on_interval_change,
+            }
+            div {
+                class: "flex gap-2",
+                input {
+                    class: "flex-1 min-w-0 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                    placeholder: "频道或播放列表链接",
+                    value: "{new_url}",
+                    oninput: move |e| new_url.set(e.value()),
+                }
+                button {
+                    class: "bg-blue-600 hover:bg-blue-700 text-white px-4 py-1 rounded-lg transition-colors disabled:opacity-50",
+                    disabled: new_url.read().trim().is_empty(),
+                    onclick: add,
+                    "添加"
+                }
+            }
+            if let Some(m) = message.read().as_ref() {
+                p { class: "mt-2 text-sm text-gray-400", "{m}" }
+            }
+
+            match &*subscriptions.read() {
+                Err(e) => rsx! {
+                    p { class: "mt-3 text-red-400", "{e}" }
+                },
+                Ok(list) if list.is_empty() => rsx! {
+                    p { class: "mt-3 text-sm text-gray-400", "暂无订阅" }
+                },
+                Ok(list) => rsx! {
+                    div {
+                        class: "mt-3 space-y-2",
+                        for subscription in list.iter().cloned() {
+                            SubscriptionItem {
+                                key: "{subscription.id}",
+                                subscription,
+                                on_change: move |_| reload(),
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn SubscriptionItem(subscription: Subscription, on_change: EventHandler<()>) -> Element {
+    let queue = use_context::<DownloadQueue>();
+    let mut syncing = use_signal(|| false);
+    let mut result = use_signal(|| None::<String>);
+
+    let update = {
+        let subscription = subscription.clone();
+        move |apply: fn(&mut Subscription, Option<String>), value: Option<String>| {
+            let mut subscription = subscription.clone();
+            apply(&mut subscription, value);
+            result.set(save_subscription(&subscription));
+            on_change.call(());
+        }
+    };
+
+    let sync_now = {
+        let subscription = subscription.clone();
+        move |_| {
+            let subscription = subscription.clone();
+            let queue = queue.clone();
+            async move {
+                syncing.set(true);
+                let outcome =
+                    run_blocking(move || subscriptions::sync_and_record(&subscription, &queue))
+                        .await;
+                result.set(Some(match outcome {
+                    Ok(0) => "没有新视频".to_string(),
+                    Ok(n) => format!("已加入 {} 个新视频", n),
+                    Err(e) => e,
+                }));
+                syncing.set(false);
+                on_change.call(());
+            }
+        }
+    };
+
+    let pick_folder = {
+        let update = update.clone();
+        move |_| {
+            let mut update = update.clone();
+            async move {
+                if let Some(folder) = AsyncFileDialog::new()
+                    .set_title("选择订阅的下载文件夹")
+                    .pick_folder()
+                    .await
+                {
+                    let path = folder.path().to_string_lossy().to_string();
+                    update(|s, v| s.download_path = v, Some(path));
+                }
+            }
+        }
+    };
+
+    let status = [
+        Some(match subscription.last_checked {
+            Some(t) => format!("上次检查 {}", format_time(t)),
+            None => "尚未检查".to_string(),
+        }),
+        subscription.last_error.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ");
+
+    rsx! {
+        div {
+            class: "bg-gray-700 rounded-lg p-3 text-sm space-y-2",
+            div {
+                class: "flex items-center gap-3",
+                input {
+                    r#type: "checkbox",
+                    class: "accent-blue-600",
+                    title: "启用",
+                    checked: subscription.enabled,
+                    onchange: {
+                        let subscription = subscription.clone();
+                        move |e: FormEvent| {
+                            let subscription = Subscription { enabled: e.checked(), ..subscription.clone() };
+                            result.set(save_subscription(&subscription));
+                            on_change.call(());
+                        }
+                    },
+                }
+                span {
+                    class: "flex-1 truncate text-gray-200",
+                    title: "{subscription.url}",
+                    {subscription.name.clone().unwrap_or(subscription.url.clone())}
+                }
+                button {
+                    class: "text-gray-400 hover:text-white transition-colors disabled:opacity-50",
+                    disabled: syncing(),
+                    onclick: sync_now,
+                    if syncing() { "检查中..." } else { "立即检查" }
+                }
+                button {
+                    class: "text-gray-400 hover:text-red-400 transition-colors",
+                    onclick: move |_| {
+                        if let Ok(history) = HistoryDb::open() {
+                            let _ = history.delete_subscription(subscription.id);
+                        }
+                        on_change.call(());
+                    },
+                    "删除"
+                }
+            }
+            p {
+                class: if subscription.last_error.is_some() { "text-xs text-red-400" } else { "text-xs text-gray-400" },
+                "{status}"
+            }
+            div {
+                class: "flex items-center gap-2",
+                span { class: "text-gray-400", "画质" }
+                select {
+                    class: "bg-gray-800 text-white rounded px-2 py-1 border border-gray-600",
+                    onchange: {
+                        let mut update = update.clone();
+                        move |e: FormEvent| {
+                            let value = Some(e.value()).filter(|v| !v.is_empty());
+                            update(|s, v| s.quality = v, value)
+                        }
+                    },
+                    for (value, text) in [("", "跟随设置"), ("best", "最佳画质"), ("normal", "普通画质")] {
+                        option {
+                            value: "{value}",
+                            selected: subscription.quality.as_deref().unwrap_or_default() == value,
+                            "{text}"
+                        }
+                    }
+                }
+                span {
+                    class: "flex-1 truncate text-gray-400 ml-2",
+                    title: subscription.download_path.clone().unwrap_or_default(),
+                    {subscription.download_path.clone().unwrap_or("下载到默认路径".to_string())}
+                }
+                button {
+                    class: "text-gray-400 hover:text-white transition-colors",
+                    onclick: pick_folder,
+                    "选择路径"
+                }
+                if subscription.download_path.is_some() {
+                    button {
+                        class: "text-gray-400 hover:text-white transition-colors",
+                        onclick: {
+                            let mut update = update.clone();
+                            move |_| update(|s, v| s.download_path = v, None)
+                        },
+                        "默认"
+                    }
+                }
+            }
+            if let Some(r) = result.read().as_ref() {
+                p { class: "text-xs text-gray-300", "{r}" }
+            }
+        }
+    }
+}
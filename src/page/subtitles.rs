@@ -0,0 +1,105 @@
+use crate::downloader::metadata::VideoInfo;
+use crate::downloader::DownloadRequest;
+use dioxus::prelude::*;
+
+/// 按视频实际提供的字幕选择本次下载的语言
+#[component]
+pub fn SubtitlePicker(info: VideoInfo, request: Signal<DownloadRequest>) -> Element {
+    let subtitles = info.subtitle_languages();
+    let captions = info.automatic_caption_languages();
+    let selected = request.read().subtitles.language_list();
+
+    rsx! {
+        details {
+            class: "mt-4",
+            summary {
+                class: "text-sm text-gray-400 cursor-pointer hover:text-white",
+                {format!("字幕（{} 种，自动生成 {} 种）", subtitles.len(), captions.len())}
+            }
+            div {
+                class: "mt-3 space-y-3 text-sm",
+
+                div {
+                    class: "flex items-center justify-between gap-4",
+                    span { class: "text-gray-300", "语言" }
+                    input {
+                        class: "flex-1 bg-gray-700 text-white rounded-lg px-3 py-1 border border-gray-600 focus:border-blue-500",
+                        value: "{request.read().subtitles.languages}",
+                        placeholder: "如 zh-Hans,en，支持正则",
+                        onchange: move |e| {
+                            let mut request = request.write();
+                            request.subtitles.languages = e.value();
+                            request.subtitles.disable_if_empty();
+                        },
+                    }
+                }
+
+                if subtitles.is_empty() {
+                    p { class: "text-gray-400", "该视频没有上传者提供的字幕" }
+                } else {
+                    LanguageList {
+                        title: "字幕".to_string(),
+                        languages: subtitles,
+                        selected: selected.clone(),
+                        on_toggle: move |code: String| {
+                            let mut request = request.write();
+                            if request.subtitles.toggle_language(&code) {
+                                request.subtitles.write_subs = true;
+                            }
+                        },
+                    }
+                }
+
+                if !captions.is_empty() {
+                    LanguageList {
+                        title: "自动生成".to_string(),
+                        languages: captions,
+                        selected,
+                        on_toggle: move |code: String| {
+                            let mut request = request.write();
+                            if request.subtitles.toggle_language(&code) {
+                                request.subtitles.write_auto_subs = true;
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LanguageList(
+    title: String,
+    languages: Vec<(String, String)>,
+    selected: Vec<String>,
+    on_toggle: EventHandler<String>,
+) -> Element {
+    rsx! {
+        div {
+            p { class: "text-gray-400 mb-1", "{title}" }
+            div {
+                class: "flex flex-wrap gap-2 max-h-[120px] overflow-y-auto",
+                for (code, name) in languages {
+                    {
+                        let active = selected.contains(&code);
+                        let class = if active {
+                            "bg-blue-600 text-white"
+                        } else {
+                            "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                        };
+                        rsx! {
+                            button {
+                                key: "{code}",
+                                class: "px-2 py-1 rounded text-xs transition-colors {class}",
+                                title: "{code}",
+                                onclick: move |_| on_toggle.call(code.clone()),
+                                "{name}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::downloader::template::{self, DEFAULT_TEMPLATE, FIELDS};
+use dioxus::prelude::*;
+
+/// 文件名模板编辑器：字段面板、示例预览，校验通过才保存
+#[component]
+pub fn TemplateEditor(value: String, on_save: EventHandler<String>) -> Element {
+    let mut draft = use_signal(|| value.clone());
+    let validation = template::validate(&draft.read());
+    let preview = template::render(&draft.read(), &template::sample_fields());
+    let changed = *draft.read() != value;
+
+    rsx! {
+        div {
+            class: "bg-gray-800 rounded-xl p-4 mb-4 shadow-lg border border-gray-700",
+            h2 {
+                class: "text-lg font-semibold mb-3 text-white",
+                "文件名模板"
+            }
+            input {
+                class: "w-full bg-gray-700 text-white rounded-lg px-3 py-2 border border-gray-600 focus:border-blue-500 font-mono text-sm",
+                value: "{draft}",
+                oninput: move |e| draft.set(e.value()),
+            }
+            div {
+                class: "mt-2 flex flex-wrap gap-2",
+                for (field, label, text) in FIELDS {
+                    button {
+                        key: "{field}",
+                        class: "px-2 py-1 rounded text-xs bg-gray-700 text-gray-300 hover:bg-gray-600 transition-colors",
+                        title: "{text}",
+                        onclick: move |_| draft.write().push_str(text),
+                        "{label}"
+                    }
+                }
+            }
+            p {
+                class: "mt-3 text-sm text-gray-400 break-all",
+                "预览: "
+                span {
+                    class: "text-white",
+                    {preview.unwrap_or_default()}
+                }
+            }
+            if let Err(e) = &validation {
+                p { class: "mt-1 text-sm text-red-400", "{e}" }
+            }
+            div {
+                class: "mt-3 flex gap-2",
+                button {
+                    class: "bg-blue-600 hover:bg-blue-700 text-white px-4 py-1 rounded-lg transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                    disabled: validation.is_err() || !changed,
+                    onclick: move |_| on_save.call(draft.read().trim().to_string()),
+                    "保存"
+                }
+                button {
+                    class: "bg-gray-700 hover:bg-gray-600 text-white px-4 py-1 rounded-lg transition-colors",
+                    onclick: move |_| draft.set(DEFAULT_TEMPLATE.to_string()),
+                    "恢复默认"
+                }
+            }
+            p {
+                class: "mt-2 text-sm text-gray-400",
+                "使用 yt-dlp 输出模板语法，可用 / 创建子文件夹；预览使用示例数据"
+            }
+        }
+    }
+}